@@ -1,6 +1,19 @@
-use crate::{LcuClient, Result};
+use crate::{
+    LcuClient, Result,
+    events::champ_select::{ChampSelectData, SwapKind},
+};
+
+const SESSION_API: &str = "/lol-champ-select/v1/session";
 
 impl LcuClient {
+    pub async fn get_champ_select_session(&self) -> Result<ChampSelectData> {
+        Ok(self
+            .get(SESSION_API)
+            .await?
+            .json::<ChampSelectData>()
+            .await?)
+    }
+
     pub async fn swap_champion(&self, champion_id: u16) -> Result<()> {
         self.post(&format!(
             "/lol-champ-select/v1/session/bench/swap/{champion_id}"
@@ -9,10 +22,49 @@ impl LcuClient {
         Ok(())
     }
 
-    pub async fn pick_champion(&self, champion_id: u16, action_id: u8) -> Result<()> {
+    pub async fn reroll_champion(&self) -> Result<()> {
+        self.post("/lol-champ-select/v1/session/my-selection/reroll")
+            .await?;
+        Ok(())
+    }
+
+    /// 修改召唤师技能(spell1Id/spell2Id)和皮肤(selectedSkinId)
+    pub async fn update_my_selection(&self, selection: &serde_json::Value) -> Result<()> {
+        self.patch_json("/lol-champ-select/v1/session/my-selection", selection)
+            .await?;
+        Ok(())
+    }
+
+    /// 接受或拒绝交换英雄、选择顺序或位置的请求
+    pub async fn answer_swap(&self, kind: SwapKind, id: u64, accept: bool) -> Result<()> {
+        let answer = if accept { "accept" } else { "decline" };
+        self.post(&format!(
+            "/lol-champ-select/v1/session/{}/{id}/{answer}",
+            kind.api()
+        ))
+        .await?;
+        Ok(())
+    }
+
+    /// completed为false时仅预选英雄
+    pub async fn pick_champion(
+        &self,
+        champion_id: u16,
+        action_id: u8,
+        completed: bool,
+    ) -> Result<()> {
+        self.patch_json(
+            &format!("/lol-champ-select/v1/session/actions/{action_id}"),
+            &serde_json::json!({"completed": completed, "type": "pick", "championId": champion_id}),
+        )
+        .await?;
+        Ok(())
+    }
+
+    pub async fn ban_champion(&self, champion_id: u16, action_id: u8) -> Result<()> {
         self.patch_json(
             &format!("/lol-champ-select/v1/session/actions/{action_id}"),
-            &serde_json::json!({"completed": true, "type": "pick", "championId": champion_id}),
+            &serde_json::json!({"completed": true, "type": "ban", "championId": champion_id}),
         )
         .await?;
         Ok(())
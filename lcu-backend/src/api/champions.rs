@@ -1,6 +1,17 @@
+use std::collections::HashMap;
+
+use serde::Deserialize;
+
 use crate::{LcuClient, Result, context::Champion};
 
 const OWNED_CHAMPIONS_API: &str = "/lol-champions/v1/owned-champions-minimal";
+const CHAMPION_SUMMARY_API: &str = "/lol-game-data/assets/v1/champion-summary.json";
+
+#[derive(Debug, Deserialize)]
+struct ChampionSummary {
+    id: i32, // 无英雄时为-1
+    name: String,
+}
 
 #[derive(Debug, serde::Deserialize)]
 pub struct OwnedChampionRow {
@@ -9,7 +20,56 @@ pub struct OwnedChampionRow {
     pub title: String,
 }
 
+#[derive(Debug, Deserialize)]
+pub struct Ownership {
+    pub owned: bool,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct Chroma {
+    pub id: u32,
+    pub ownership: Ownership,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Skin {
+    pub id: u32,
+    pub is_base: bool,
+    pub ownership: Ownership,
+    #[serde(default)]
+    pub chromas: Vec<Chroma>,
+}
+
 impl LcuClient {
+    pub async fn get_champion_skins(
+        &self,
+        summoner_id: u64,
+        champion_id: u16,
+    ) -> Result<Vec<Skin>> {
+        Ok(self
+            .get(&format!(
+                "/lol-champions/v1/inventories/{summoner_id}/champions/{champion_id}/skins"
+            ))
+            .await?
+            .json::<Vec<Skin>>()
+            .await?)
+    }
+
+    /// 全部英雄ID -> 名称
+    pub async fn get_champion_names(&self) -> Result<HashMap<u16, String>> {
+        let data = self
+            .get(CHAMPION_SUMMARY_API)
+            .await?
+            .json::<Vec<ChampionSummary>>()
+            .await?;
+        Ok(data
+            .into_iter()
+            .filter(|champion| champion.id > 0)
+            .map(|champion| (champion.id as u16, champion.name))
+            .collect())
+    }
+
     pub async fn get_owned_champions(&self) -> Result<Vec<Champion>> {
         let response = self.get(OWNED_CHAMPIONS_API).await?;
         let data = response.json::<Vec<OwnedChampionRow>>().await?;
@@ -1,6 +1,14 @@
 use log::info;
+use serde::Deserialize;
 
-use crate::LcuClient;
+use crate::{CONTEXT, LcuClient};
+
+#[derive(Debug, Deserialize)]
+struct Conversation {
+    id: String,
+    #[serde(rename = "type")]
+    conversation_type: String,
+}
 
 #[derive(serde::Serialize)]
 pub struct MessageBody {
@@ -19,6 +27,24 @@ impl MessageBody {
 }
 
 impl LcuClient {
+    /// 英雄选择聊天的ID，优先使用聊天事件中记录的ID
+    pub(crate) async fn get_champ_select_conversation_id(&self) -> Option<String> {
+        if let Some(id) = CONTEXT.champ_select_conversation.read().unwrap().clone() {
+            return Some(id);
+        }
+        let conversations = self
+            .get("/lol-chat/v1/conversations")
+            .await
+            .ok()?
+            .json::<Vec<Conversation>>()
+            .await
+            .ok()?;
+        conversations
+            .into_iter()
+            .find(|conversation| conversation.conversation_type == "championSelect")
+            .map(|conversation| conversation.id)
+    }
+
     pub(crate) async fn send_message(&self, conversation_id: &str, message: &str) {
         let _ = self
             .post_json(
@@ -0,0 +1,310 @@
+use std::{
+    collections::{HashMap, HashSet},
+    fmt::Display,
+};
+
+use futures_util::{StreamExt, stream};
+use log::{debug, warn};
+use serde::Deserialize;
+
+use crate::{CONTEXT, LcuClient, Result, store::STORE};
+
+// 建立索引时查询自己的近期对局数
+const ENCOUNTER_GAMES: usize = 20;
+// 同时查询的对局详情数
+const ENCOUNTER_CONCURRENCY: usize = 2;
+
+#[derive(Debug, Deserialize)]
+struct ParticipantPlayer {
+    #[serde(default)]
+    puuid: String,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ParticipantIdentity {
+    participant_id: u8,
+    player: ParticipantPlayer,
+}
+
+#[derive(Debug, Deserialize)]
+struct ParticipantResult {
+    win: bool,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct GameParticipant {
+    participant_id: u8,
+    team_id: u16,
+    #[serde(default)]
+    champion_id: u16,
+    stats: ParticipantResult,
+}
+
+/// 包含全部玩家的对局详情
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GameDetail {
+    game_id: u64,
+    #[serde(default)]
+    game_creation: i64,
+    participant_identities: Vec<ParticipantIdentity>,
+    participants: Vec<GameParticipant>,
+}
+
+impl GameDetail {
+    /// 每位玩家的 (puuid, 队伍, 英雄, 是否胜利)
+    fn players(&self) -> impl Iterator<Item = (&str, u16, u16, bool)> {
+        self.participants.iter().filter_map(|participant| {
+            let identity = self
+                .participant_identities
+                .iter()
+                .find(|identity| identity.participant_id == participant.participant_id)?;
+            Some((
+                identity.player.puuid.as_str(),
+                participant.team_id,
+                participant.champion_id,
+                participant.stats.win,
+            ))
+        })
+    }
+}
+
+/// 和某位玩家的一次相遇
+#[derive(Debug, Clone)]
+pub struct Encounter {
+    pub game_id: u64,
+    pub game_creation: i64,
+    pub champion_id: u16, // 对方使用的英雄
+    pub ally: bool,
+    pub won: bool, // 自己是否胜利
+}
+
+/// 和某位玩家的相遇统计
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct EncounterSummary {
+    pub ally_games: u32,
+    pub ally_wins: u32,
+    pub enemy_games: u32,
+    pub enemy_wins: u32,
+}
+
+impl Display for EncounterSummary {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let mut parts = vec![];
+        if self.ally_games > 0 {
+            parts.push(format!(
+                "队友{}胜{}负",
+                self.ally_wins,
+                self.ally_games - self.ally_wins
+            ));
+        }
+        if self.enemy_games > 0 {
+            parts.push(format!(
+                "对手{}胜{}负",
+                self.enemy_wins,
+                self.enemy_games - self.enemy_wins
+            ));
+        }
+        write!(
+            f,
+            "近期遇到过{}次（{}）",
+            self.ally_games + self.enemy_games,
+            parts.join("，")
+        )
+    }
+}
+
+/// 自己近期对局中出现过的玩家
+#[derive(Debug, Default)]
+pub struct EncounterIndex {
+    games: HashSet<u64>,
+    players: HashMap<String, Vec<Encounter>>,
+}
+
+impl EncounterIndex {
+    fn contains_game(&self, game_id: u64) -> bool {
+        self.games.contains(&game_id)
+    }
+
+    /// 将对局中除自己以外的玩家加入索引
+    fn insert(&mut self, me: &str, game: &GameDetail) {
+        if self.contains_game(game.game_id) {
+            return;
+        }
+        // 玩家信息缺失或被隐藏时不记录，下次重新查询
+        let Some((_, my_team, _, won)) = game.players().find(|(puuid, ..)| *puuid == me) else {
+            return;
+        };
+        self.games.insert(game.game_id);
+        for (puuid, team_id, champion_id, _) in game.players() {
+            if puuid == me || puuid.is_empty() {
+                continue;
+            }
+            self.players
+                .entry(puuid.to_string())
+                .or_default()
+                .push(Encounter {
+                    game_id: game.game_id,
+                    game_creation: game.game_creation,
+                    champion_id,
+                    ally: team_id == my_team,
+                    won,
+                });
+        }
+    }
+
+    pub fn encounters(&self, puuid: &str) -> &[Encounter] {
+        self.players.get(puuid).map_or(&[], Vec::as_slice)
+    }
+
+    /// 和指定玩家的相遇统计，没有遇到过时为None
+    pub fn summary(&self, puuid: &str) -> Option<EncounterSummary> {
+        let encounters = self.encounters(puuid);
+        if encounters.is_empty() {
+            return None;
+        }
+        let mut summary = EncounterSummary::default();
+        for encounter in encounters {
+            if encounter.ally {
+                summary.ally_games += 1;
+                summary.ally_wins += encounter.won as u32;
+            } else {
+                summary.enemy_games += 1;
+                summary.enemy_wins += encounter.won as u32;
+            }
+        }
+        Some(summary)
+    }
+}
+
+impl LcuClient {
+    /// 查询对局详情的原始 JSON，本地数据库中已有时不再请求
+    pub async fn get_game_data(&self, game_id: u64) -> Result<String> {
+        match STORE.load_game(game_id) {
+            Ok(Some(data)) => return Ok(data),
+            Ok(None) => {}
+            Err(e) => warn!("读取本地对局详情失败: {e}"),
+        }
+        let data = self
+            .get(&format!("/lol-match-history/v1/games/{game_id}"))
+            .await?
+            .text()
+            .await?;
+        let game = serde_json::from_str::<GameDetail>(&data)?;
+        // 玩家信息全部缺失的详情不保存，下次重新查询
+        if game.players().any(|(puuid, ..)| !puuid.is_empty())
+            && let Err(e) = STORE.insert_game(game_id, game.game_creation, &data)
+        {
+            warn!("保存对局详情失败: {e}");
+        }
+        Ok(data)
+    }
+
+    pub(crate) async fn get_game(&self, game_id: u64) -> Result<GameDetail> {
+        Ok(serde_json::from_str(&self.get_game_data(game_id).await?)?)
+    }
+
+    /// 查询自己的近期对局，将尚未索引的对局加入索引
+    pub(crate) async fn refresh_encounter_index(&self) {
+        let me = CONTEXT.me.read().unwrap().puuid.clone();
+        if me.is_empty() {
+            return;
+        }
+        let matches = match self.get_matches(&me, 0, ENCOUNTER_GAMES).await {
+            Ok(matches) => matches,
+            Err(e) => {
+                warn!("查询近期对局失败: {e}");
+                return;
+            }
+        };
+        let game_ids = {
+            let index = CONTEXT.encounter_index.read().unwrap();
+            matches
+                .teams_since(0)
+                .map(|(game_id, _)| game_id)
+                .filter(|game_id| !index.contains_game(*game_id))
+                .collect::<Vec<_>>()
+        };
+        if game_ids.is_empty() {
+            return;
+        }
+        debug!("索引{}场近期对局", game_ids.len());
+        let games = stream::iter(game_ids)
+            .map(|game_id| self.get_game(game_id))
+            .buffer_unordered(ENCOUNTER_CONCURRENCY)
+            .collect::<Vec<_>>()
+            .await;
+        let mut index = CONTEXT.encounter_index.write().unwrap();
+        for game in games {
+            match game {
+                Ok(game) => index.insert(&me, &game),
+                Err(e) => warn!("查询对局详情失败: {e}"),
+            }
+        }
+    }
+}
+
+#[test]
+fn test_encounter_index() {
+    use crate::fixtures;
+
+    // 前几位玩家替换为指定的 (puuid, 队伍, 是否胜利)
+    let game = |game_id: u64, players: &[(&str, u16, bool)]| -> GameDetail {
+        fixtures::load(fixtures::GAME, |game| {
+            game["gameId"] = game_id.into();
+            for (i, (puuid, team_id, win)) in players.iter().enumerate() {
+                game["participantIdentities"][i]["player"]["puuid"] = (*puuid).into();
+                game["participants"][i]["teamId"] = (*team_id).into();
+                game["participants"][i]["stats"]["win"] = (*win).into();
+            }
+        })
+    };
+    let mut index = EncounterIndex::default();
+    index.insert(
+        "me",
+        &game(1, &[("me", 100, true), ("a", 100, true), ("b", 200, false)]),
+    );
+    index.insert(
+        "me",
+        &game(
+            2,
+            &[("me", 100, false), ("a", 200, true), ("c", 100, false)],
+        ),
+    );
+    // 同一局不会重复统计
+    index.insert(
+        "me",
+        &game(
+            2,
+            &[("me", 100, false), ("a", 200, true), ("c", 100, false)],
+        ),
+    );
+
+    // 对局中找不到自己时不记录，之后仍可加入索引
+    index.insert("me", &game(3, &[("", 100, true), ("d", 200, false)]));
+    assert!(!index.contains_game(3));
+    assert!(index.summary("d").is_none());
+    index.insert("me", &game(3, &[("me", 100, true), ("d", 200, false)]));
+    assert!(index.contains_game(3));
+    assert_eq!(index.encounters("d").len(), 1);
+
+    let summary = index.summary("a").unwrap();
+    assert_eq!(
+        summary,
+        EncounterSummary {
+            ally_games: 1,
+            ally_wins: 1,
+            enemy_games: 1,
+            enemy_wins: 0,
+        }
+    );
+    assert_eq!(
+        summary.to_string(),
+        "近期遇到过2次（队友1胜0负，对手0胜1负）"
+    );
+    assert_eq!(index.encounters("b").len(), 1);
+    assert!(index.summary("me").is_none());
+    assert!(index.summary("e").is_none());
+}
@@ -0,0 +1,57 @@
+use std::sync::atomic::Ordering;
+use std::time::Duration;
+
+use log::{error, info, warn};
+use tokio::time::sleep;
+
+use crate::{CONTEXT, GamePhase, LcuClient, Result};
+
+const PHASE_API: &str = "/lol-gameflow/v1/gameflow-phase";
+const RECONNECT_API: &str = "/lol-gameflow/v1/reconnect";
+// 自动重新连接的最大尝试次数
+const MAX_RECONNECT_ATTEMPTS: u8 = 3;
+// 每次尝试后等待客户端进入游戏的时间
+const RECONNECT_INTERVAL: Duration = Duration::from_secs(10);
+
+impl LcuClient {
+    pub async fn get_game_phase(&self) -> Result<GamePhase> {
+        Ok(self.get(PHASE_API).await?.json::<GamePhase>().await?)
+    }
+
+    /// 在后台自动重新连接，避免等待期间阻塞其他事件的处理，离开重新连接状态时取消
+    pub(crate) fn spawn_auto_reconnect(&self) {
+        if !CONTEXT.auto_reconnect.load(Ordering::Relaxed) {
+            return;
+        }
+        let client = self.clone();
+        let task = tokio::spawn(async move { client.auto_reconnect().await });
+        if let Some(previous) = CONTEXT
+            .reconnect_task
+            .lock()
+            .unwrap()
+            .replace(task.abort_handle())
+        {
+            previous.abort();
+        }
+    }
+
+    /// 掉线后自动重新连接，每次尝试后检查是否仍处于重新连接状态
+    pub async fn auto_reconnect(&self) {
+        for attempt in 1..=MAX_RECONNECT_ATTEMPTS {
+            info!("尝试重新连接游戏({attempt}/{MAX_RECONNECT_ATTEMPTS})");
+            if let Err(e) = self.post(RECONNECT_API).await {
+                error!("重新连接失败: {e}");
+            }
+            sleep(RECONNECT_INTERVAL).await;
+            match self.get_game_phase().await {
+                Ok(GamePhase::Reconnect) => {}
+                Ok(_) => {
+                    info!("已重新连接游戏");
+                    return;
+                }
+                Err(e) => error!("获取客户端状态失败: {e}"),
+            }
+        }
+        warn!("重新连接失败 {MAX_RECONNECT_ATTEMPTS} 次，请手动重新连接");
+    }
+}
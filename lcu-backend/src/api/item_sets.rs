@@ -0,0 +1,207 @@
+use std::hash::{BuildHasher, RandomState};
+
+use log::info;
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
+
+use crate::{
+    CONTEXT, LcuClient, LcuError, Position, Result,
+    context::LoadoutTarget,
+    library::{self, ITEM_SETS, MANAGED_PREFIX},
+};
+
+/// 出装方案中的一组装备，同一装备出现多次表示数量
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ItemBlock {
+    pub name: String,
+    pub items: Vec<u32>,
+}
+
+/// 出装库中的出装方案，英雄、模式和位置用于锁定英雄后选择出装方案
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ItemSet {
+    pub title: String,
+    #[serde(flatten)]
+    pub target: LoadoutTarget,
+    pub blocks: Vec<ItemBlock>,
+}
+
+impl ItemSet {
+    /// 出装库中适用于指定英雄、模式和位置的全部出装方案
+    pub fn matching<'a>(
+        sets: &'a [ItemSet],
+        champion_id: u16,
+        game_mode: &str,
+        position: Option<Position>,
+    ) -> Vec<&'a ItemSet> {
+        sets.iter()
+            .filter(|set| set.target.matches(champion_id, game_mode, position))
+            .collect()
+    }
+
+    /// 转换为客户端格式，连续相同的装备合并为数量
+    fn to_client(&self, title: String, champion_id: Option<u16>) -> Value {
+        let blocks = self
+            .blocks
+            .iter()
+            .map(|block| {
+                let mut items: Vec<(u32, u32)> = vec![];
+                for &id in &block.items {
+                    match items.last_mut() {
+                        Some((last, count)) if *last == id => *count += 1,
+                        _ => items.push((id, 1)),
+                    }
+                }
+                json!({
+                    "type": block.name,
+                    "items": items
+                        .iter()
+                        .map(|(id, count)| json!({"id": id.to_string(), "count": count}))
+                        .collect::<Vec<_>>(),
+                })
+            })
+            .collect::<Vec<_>>();
+        json!({
+            "title": title,
+            "uid": format!("{:016x}", RandomState::new().hash_one(&title)),
+            "associatedChampions": champion_id.into_iter().collect::<Vec<_>>(),
+            "associatedMaps": [],
+            "blocks": blocks,
+            "map": "any",
+            "mode": "any",
+            "preferredItemSlots": [],
+            "sortrank": 0,
+            "startedFrom": "blank",
+            "type": "custom",
+        })
+    }
+
+    /// 从客户端格式转换，数量展开为重复的装备
+    fn from_client(set: &Value) -> Option<Self> {
+        let blocks = set["blocks"]
+            .as_array()?
+            .iter()
+            .map(|block| ItemBlock {
+                name: block["type"].as_str().unwrap_or_default().to_string(),
+                items: block["items"]
+                    .as_array()
+                    .into_iter()
+                    .flatten()
+                    .flat_map(|item| {
+                        let id = item["id"].as_str().and_then(|id| id.parse().ok());
+                        let count = item["count"].as_u64().unwrap_or(1) as usize;
+                        std::iter::repeat_n(id, count).flatten()
+                    })
+                    .collect(),
+            })
+            .collect();
+        let champion_id = set["associatedChampions"]
+            .as_array()
+            .and_then(|champions| champions.first())
+            .and_then(Value::as_u64)
+            .map(|id| id as u16);
+        Some(ItemSet {
+            title: set["title"].as_str()?.to_string(),
+            target: LoadoutTarget {
+                champion_id,
+                ..Default::default()
+            },
+            blocks,
+        })
+    }
+}
+
+fn is_managed(set: &Value) -> bool {
+    set["title"]
+        .as_str()
+        .is_some_and(|title| title.starts_with(MANAGED_PREFIX))
+}
+
+impl LcuClient {
+    fn item_sets_api() -> String {
+        let summoner_id = CONTEXT.me.read().unwrap().summoner_id;
+        format!("/lol-item-sets/v1/item-sets/{summoner_id}/sets")
+    }
+
+    /// 客户端保存的全部出装方案，包含accountId等需要原样写回的字段
+    async fn get_item_sets(&self) -> Result<Value> {
+        Ok(self
+            .get(&Self::item_sets_api())
+            .await?
+            .json::<Value>()
+            .await?)
+    }
+
+    async fn put_item_sets(&self, sets: &Value) -> Result<()> {
+        self.put_json(&Self::item_sets_api(), sets).await?;
+        Ok(())
+    }
+
+    /// 将出装方案写入客户端，并移除之前写入的助手出装方案
+    pub async fn set_managed_item_sets(&self, champion_id: u16, sets: &[&ItemSet]) -> Result<()> {
+        let mut data = self.get_item_sets().await?;
+        let Some(item_sets) = data["itemSets"].as_array_mut() else {
+            return Err(LcuError::ResponseError("出装方案格式错误".to_string()));
+        };
+        item_sets.retain(|set| !is_managed(set));
+        item_sets.extend(sets.iter().map(|set| {
+            set.to_client(format!("{MANAGED_PREFIX} {}", set.title), Some(champion_id))
+        }));
+        self.put_item_sets(&data).await
+    }
+
+    /// 将客户端中的出装方案导入出装库，跳过助手写入的出装方案
+    pub async fn import_item_sets(&self) -> Result<usize> {
+        let data = self.get_item_sets().await?;
+        let mut count = 0;
+        for set in data["itemSets"].as_array().into_iter().flatten() {
+            if is_managed(set) {
+                continue;
+            }
+            let Some(item_set) = ItemSet::from_client(set) else {
+                continue;
+            };
+            let path = library::save(ITEM_SETS, &item_set.title, &item_set)?;
+            info!("已导入出装方案: {} -> {}", item_set.title, path.display());
+            count += 1;
+        }
+        Ok(count)
+    }
+
+    /// 将出装库中的出装方案导出到客户端，同名方案会被覆盖
+    pub async fn export_item_set(&self, title: &str) -> Result<()> {
+        let item_set = library::load::<ItemSet>(ITEM_SETS)
+            .into_iter()
+            .find(|set| set.title == title)
+            .ok_or_else(|| LcuError::LibraryError(format!("出装库中没有出装方案: {title}")))?;
+        let mut data = self.get_item_sets().await?;
+        let Some(item_sets) = data["itemSets"].as_array_mut() else {
+            return Err(LcuError::ResponseError("出装方案格式错误".to_string()));
+        };
+        item_sets.retain(|set| set["title"].as_str() != Some(title));
+        item_sets.push(item_set.to_client(title.to_string(), item_set.target.champion_id));
+        self.put_item_sets(&data).await
+    }
+}
+
+#[test]
+fn test_item_set_conversion() {
+    let item_set = ItemSet {
+        title: "阿狸".to_string(),
+        target: LoadoutTarget {
+            champion_id: Some(103),
+            ..Default::default()
+        },
+        blocks: vec![ItemBlock {
+            name: "出门装".to_string(),
+            items: vec![1056, 2003, 2003],
+        }],
+    };
+    let value = item_set.to_client("阿狸".to_string(), Some(103));
+    assert_eq!(value["blocks"][0]["items"][1]["count"], 2);
+    assert_eq!(value["associatedChampions"][0], 103);
+
+    let decoded = ItemSet::from_client(&value).unwrap();
+    assert_eq!(decoded.target.champion_id, Some(103));
+    assert_eq!(decoded.blocks[0].items, vec![1056, 2003, 2003]);
+}
@@ -1,115 +1,545 @@
-use std::fmt::Display;
+use std::collections::{BTreeMap, HashMap};
+use std::sync::atomic::Ordering;
+
+use log::{debug, info, warn};
 
 use crate::Result;
 use serde::de::Error;
-use serde::{Deserialize, Deserializer};
+use serde::{Deserialize, Deserializer, Serialize};
+
+use crate::{
+    CONTEXT, LcuClient, Position,
+    api::{
+        encounters::EncounterSummary,
+        ranked::{RankedQueue, RankedStats},
+    },
+    context::Summoner,
+    store::{MatchRecord, MatchStore, STORE},
+    template::{TemplateData, TemplateValue},
+};
 
-use crate::{LcuClient, context::Summoner};
+/// 常用英雄的场次和胜场
+#[derive(Debug, Default, Clone)]
+pub struct ChampionUsage {
+    pub champion_id: u16,
+    pub name: String, // 分析时根据英雄ID填充
+    pub games: u32,
+    pub wins: u32,
+}
 
 #[derive(Debug, Default)]
 pub struct PlayerScore {
-    name: String, // 玩家名称
-    kda: f32,     // (击杀 + 助攻) / (死亡 + 1)
-    dpm: f32,     // 分均伤害
-    repeats: i8,  // 大于0表示连胜，否则连败
-    wins: u32,    // 胜场
-    total: u32,   // 总场次
+    pub puuid: String,
+    pub name: String,                         // 玩家名称
+    pub kda: f32,                             // (击杀 + 助攻) / (死亡 + 1)
+    pub dpm: f32,                             // 分均伤害
+    pub repeats: i8,                          // 大于0表示连胜，否则连败
+    pub wins: u32,                            // 胜场
+    pub total: u32,                           // 总场次
+    pub cs_per_min: f32,                      // 分均补刀，包含野怪
+    pub gpm: f32,                             // 分均经济
+    pub vision: f32,                          // 场均视野得分
+    pub kill_participation: Option<f32>,      // 参团率，对局数据中没有队友时为None
+    pub damage_share: Option<f32>,            // 伤害占比，对局数据中没有队友时为None
+    pub champions: Vec<ChampionUsage>,        // 按场次排序的英雄使用情况
+    pub roles: Vec<(Position, u32)>,          // 按场次排序的位置分布
+    pub avg_duration: u32,                    // 平均时长，秒
+    pub scanned: u32,                         // 查询过的对局数，包括不符合条件的
+    pub ranked: Option<RankedStats>,          // 段位信息，查询失败时为None
+    pub premade: Option<u8>,                  // 组队编号，同一编号的玩家一起排队
+    pub encounters: Option<EncounterSummary>, // 自己近期对局中和该玩家的相遇统计
 }
 
+// 常用英雄显示的数量
+const TOP_CHAMPIONS: usize = 3;
+
 impl PlayerScore {
     pub fn set_name(&mut self, name: &str) {
         self.name = name.to_string();
     }
+
+    /// 根据英雄ID填充常用英雄名称，找不到时使用ID
+    pub fn set_champion_names(&mut self, names: &HashMap<u16, String>) {
+        for usage in &mut self.champions {
+            usage.name = names
+                .get(&usage.champion_id)
+                .cloned()
+                .unwrap_or_else(|| usage.champion_id.to_string());
+        }
+    }
+
+    /// 用于预览模板的示例数据
+    pub fn sample() -> Self {
+        PlayerScore {
+            puuid: String::new(),
+            name: "示例玩家#1234".to_string(),
+            kda: 3.25,
+            dpm: 812.5,
+            repeats: 3,
+            wins: 12,
+            total: 20,
+            cs_per_min: 7.4,
+            gpm: 412.0,
+            vision: 21.5,
+            kill_participation: Some(58.0),
+            damage_share: Some(27.5),
+            champions: vec![
+                ChampionUsage {
+                    champion_id: 103,
+                    name: "阿狸".to_string(),
+                    games: 8,
+                    wins: 5,
+                },
+                ChampionUsage {
+                    champion_id: 238,
+                    name: "劫".to_string(),
+                    games: 5,
+                    wins: 3,
+                },
+            ],
+            roles: vec![(Position::Middle, 15), (Position::Top, 5)],
+            avg_duration: 1710,
+            scanned: 40,
+            ranked: Some(RankedStats {
+                solo: RankedQueue {
+                    tier: "GOLD".to_string(),
+                    division: "II".to_string(),
+                    league_points: 45,
+                    wins: 30,
+                    losses: 20,
+                    previous_season_end_tier: "SILVER".to_string(),
+                    previous_season_end_division: "I".to_string(),
+                    ..Default::default()
+                },
+                flex: RankedQueue::default(),
+            }),
+            premade: Some(1),
+            encounters: Some(EncounterSummary {
+                ally_games: 2,
+                ally_wins: 1,
+                enemy_games: 1,
+                enemy_wins: 1,
+            }),
+        }
+    }
+
+    /// 指定英雄的使用情况，样本中没有使用过时为None
+    pub fn champion_usage(&self, champion_id: u16) -> Option<&ChampionUsage> {
+        self.champions
+            .iter()
+            .find(|usage| usage.champion_id == champion_id)
+    }
+
+    fn champions_text(&self) -> String {
+        self.champions
+            .iter()
+            .take(TOP_CHAMPIONS)
+            .map(|usage| format!("{}({})", usage.name, usage.games))
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+
+    fn roles_text(&self) -> String {
+        self.roles
+            .iter()
+            .map(|(position, games)| {
+                format!("{}{}%", position.name(), games * 100 / self.total.max(1))
+            })
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
 }
 
-impl Display for PlayerScore {
-    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
-        let streak_text = if self.repeats > 0 { "连胜" } else { "连败" };
-        write!(
-            f,
-            "{}战绩信息：\n\
-            kda={:.2}，分均伤害={:.2}\n\
-            胜率={}/{}，{}{}",
-            self.name,
-            self.kda,
-            self.dpm,
-            self.wins,
-            self.total,
-            self.repeats.abs(),
-            streak_text
-        )
+impl TemplateData for PlayerScore {
+    const PLACEHOLDERS: &'static [(&'static str, &'static str)] = &[
+        ("name", "玩家名称"),
+        ("kda", "(击杀 + 助攻) / (死亡 + 1)"),
+        ("dpm", "分均伤害"),
+        ("wins", "胜场"),
+        ("losses", "负场"),
+        ("total", "实际分析的场次"),
+        ("win_rate", "胜率百分比"),
+        ("streak", "连胜或连败场次"),
+        ("win_streak", "是否连胜，用于条件块"),
+        ("cs_per_min", "分均补刀"),
+        ("gpm", "分均经济"),
+        ("vision", "场均视野得分"),
+        ("kp", "参团率百分比，缺少队伍数据时为空"),
+        ("damage_share", "伤害占比百分比，缺少队伍数据时为空"),
+        ("champions", "常用英雄及场次"),
+        ("roles", "位置分布"),
+        ("duration", "平均时长（分钟）"),
+        ("scanned", "查询过的对局数"),
+        ("rank", "单双排段位和胜点，未定级时为空"),
+        ("rank_win_rate", "单双排胜率百分比"),
+        ("last_rank", "上赛季单双排段位"),
+        ("series", "单双排晋级赛进度"),
+        ("flex_rank", "灵活排位段位和胜点"),
+        ("flex_win_rate", "灵活排位胜率百分比"),
+        ("premade", "组队编号，单排时为空"),
+        ("history", "和自己近期对局中的相遇记录，没有遇到过时为空"),
+    ];
+
+    fn value(&self, name: &str) -> Option<TemplateValue> {
+        let value = match name {
+            "name" => TemplateValue::Text(self.name.clone()),
+            "kda" => TemplateValue::Float(self.kda),
+            "dpm" => TemplateValue::Float(self.dpm),
+            "wins" => TemplateValue::Int(self.wins as i64),
+            "losses" => TemplateValue::Int((self.total - self.wins) as i64),
+            "total" => TemplateValue::Int(self.total as i64),
+            "win_rate" => {
+                TemplateValue::Int((self.wins * 100).checked_div(self.total).unwrap_or(0) as i64)
+            }
+            "streak" => TemplateValue::Int(self.repeats.abs() as i64),
+            "win_streak" => TemplateValue::Bool(self.repeats > 0),
+            "cs_per_min" => TemplateValue::Float(self.cs_per_min),
+            "gpm" => TemplateValue::Float(self.gpm),
+            "vision" => TemplateValue::Float(self.vision),
+            "kp" => TemplateValue::Float(self.kill_participation?),
+            "damage_share" => TemplateValue::Float(self.damage_share?),
+            "champions" => TemplateValue::Text(self.champions_text()),
+            "roles" => TemplateValue::Text(self.roles_text()),
+            "duration" => TemplateValue::Int((self.avg_duration / 60) as i64),
+            "scanned" => TemplateValue::Int(self.scanned as i64),
+            "rank" => TemplateValue::Text(self.ranked.as_ref()?.solo.rank()?),
+            "rank_win_rate" => TemplateValue::Int(self.ranked.as_ref()?.solo.win_rate()? as i64),
+            "last_rank" => TemplateValue::Text(self.ranked.as_ref()?.solo.previous_rank()?),
+            "series" => TemplateValue::Text(self.ranked.as_ref()?.solo.series()?),
+            "flex_rank" => TemplateValue::Text(self.ranked.as_ref()?.flex.rank()?),
+            "flex_win_rate" => TemplateValue::Int(self.ranked.as_ref()?.flex.win_rate()? as i64),
+            "premade" => TemplateValue::Int(self.premade? as i64),
+            "history" => TemplateValue::Text(self.encounters?.to_string()),
+            _ => return None,
+        };
+        Some(value)
     }
 }
 
-#[derive(Debug, serde::Deserialize)]
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
 struct Status {
     assists: u16,
     deaths: u16,
     kills: u16,
     win: bool,
-    #[serde(rename = "totalDamageDealtToChampions")]
     total_damage_dealt_to_champions: u32,
+    #[serde(default)]
+    total_minions_killed: u32,
+    #[serde(default)]
+    neutral_minions_killed: u32,
+    #[serde(default)]
+    gold_earned: u32,
+    #[serde(default)]
+    vision_score: u32,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct Timeline {
+    #[serde(default)]
+    lane: String,
+    #[serde(default)]
+    role: String,
 }
 
-#[derive(Debug, serde::Deserialize)]
+impl Timeline {
+    fn position(&self) -> Option<Position> {
+        match (self.lane.as_str(), self.role.as_str()) {
+            ("TOP", _) => Some(Position::Top),
+            ("JUNGLE", _) => Some(Position::Jungle),
+            ("MIDDLE" | "MID", _) => Some(Position::Middle),
+            ("BOTTOM" | "BOT", "DUO_SUPPORT" | "SUPPORT") => Some(Position::Utility),
+            ("BOTTOM" | "BOT", _) => Some(Position::Bottom),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Participant {
+    #[serde(default)]
+    team_id: u16,
+    #[serde(default)]
+    champion_id: u16,
+    stats: Status,
+    #[serde(default)]
+    timeline: Timeline,
+}
+
+#[derive(Debug, Deserialize)]
 #[serde(rename_all = "camelCase")]
 struct Match {
     game_mode: String,
-    // pub game_creation: u64, // 毫秒
+    #[serde(default)]
+    queue_id: i32,
+    #[serde(default)]
+    game_id: u64,
+    #[serde(default)]
+    game_creation: i64, // 毫秒
     game_duration: u32, // 秒
-    #[serde(
-        deserialize_with = "deserialize_status_from_participants",
-        rename = "participants"
-    )]
-    status: Status,
+    // 第一个参与者为查询的玩家，完整对局数据中还包含其他玩家
+    #[serde(deserialize_with = "deserialize_participants")]
+    participants: Vec<Participant>,
+}
+
+impl Match {
+    fn player(&self) -> &Participant {
+        &self.participants[0]
+    }
+
+    /// 用于保存到本地数据库的记录，data 为对局的原始 JSON
+    fn record(&self, data: String) -> MatchRecord {
+        let stats = &self.player().stats;
+        MatchRecord {
+            game_id: self.game_id,
+            game_creation: self.game_creation,
+            queue_id: self.queue_id,
+            game_mode: self.game_mode.clone(),
+            duration: self.game_duration,
+            win: stats.win,
+            kills: stats.kills as u32,
+            deaths: stats.deaths as u32,
+            assists: stats.assists as u32,
+            damage: stats.total_damage_dealt_to_champions,
+            gold: stats.gold_earned,
+            cs: stats.total_minions_killed + stats.neutral_minions_killed,
+            vision: stats.vision_score,
+            data,
+        }
+    }
+
+    /// 查询的玩家在该局的摘要
+    fn summary(&self, puuid: &str) -> MatchSummary {
+        let player = self.player();
+        let stats = &player.stats;
+        MatchSummary {
+            game_id: self.game_id,
+            game_creation: self.game_creation,
+            queue_id: self.queue_id,
+            game_mode: self.game_mode.clone(),
+            duration: self.game_duration,
+            puuid: puuid.to_string(),
+            champion_id: player.champion_id,
+            position: player
+                .timeline
+                .position()
+                .map_or("", Position::key)
+                .to_string(),
+            win: stats.win,
+            kills: stats.kills as u32,
+            deaths: stats.deaths as u32,
+            assists: stats.assists as u32,
+            damage: stats.total_damage_dealt_to_champions,
+            gold: stats.gold_earned,
+            cs: stats.total_minions_killed + stats.neutral_minions_killed,
+            vision: stats.vision_score,
+        }
+    }
+
+    /// 玩家所在队伍的总击杀和总伤害，对局数据中没有队友时为None
+    fn team_totals(&self) -> Option<(u32, u32)> {
+        let team_id = self.player().team_id;
+        let team = self
+            .participants
+            .iter()
+            .filter(|participant| participant.team_id == team_id)
+            .collect::<Vec<_>>();
+        if team.len() < 2 {
+            return None;
+        }
+        Some(team.iter().fold((0, 0), |(kills, damage), participant| {
+            (
+                kills + participant.stats.kills as u32,
+                damage + participant.stats.total_damage_dealt_to_champions,
+            )
+        }))
+    }
+}
+
+// 每次查询的对局数
+const PAGE_SIZE: usize = 20;
+// 最多查询的对局数，避免对局很少的玩家一直翻页
+const MAX_SCANNED_MATCHES: usize = 100;
+// 导出战绩时最多查询的对局数
+const MAX_EXPORTED_MATCHES: usize = 1000;
+// 短于该时长的对局视为重开，秒
+pub(crate) const REMAKE_DURATION: u32 = 300;
+pub const DEFAULT_ANALYSIS_GAMES: u8 = 20;
+
+/// 参与分析的对局条件
+#[derive(Debug, Clone)]
+pub struct MatchFilter {
+    // 大于0时按队列筛选，否则按游戏模式筛选
+    pub queue_id: i32,
+    pub game_mode: String,
+}
+
+impl MatchFilter {
+    fn matches(&self, m: &Match) -> bool {
+        let same_queue = if self.queue_id > 0 {
+            m.queue_id == self.queue_id
+        } else {
+            m.game_mode == self.game_mode
+        };
+        same_queue && m.game_duration >= REMAKE_DURATION
+    }
+}
+
+/// 导出战绩时每场对局的一行数据，字段顺序即导出文件的列顺序，
+/// 新增字段只能添加在末尾，避免破坏已有的分析脚本
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct MatchSummary {
+    pub game_id: u64,
+    pub game_creation: i64, // 毫秒时间戳
+    pub queue_id: i32,
+    pub game_mode: String,
+    pub duration: u32, // 秒
+    pub puuid: String,
+    pub champion_id: u16,
+    pub position: String, // 无法判断位置时为空
+    pub win: bool,
+    pub kills: u32,
+    pub deaths: u32,
+    pub assists: u32,
+    pub damage: u32, // 对英雄伤害
+    pub gold: u32,
+    pub cs: u32, // 小兵和野怪
+    pub vision: u32,
+}
+
+impl MatchSummary {
+    /// 列名和说明，顺序与字段一致
+    pub const COLUMNS: &'static [(&'static str, &'static str)] = &[
+        ("game_id", "对局ID"),
+        ("game_creation", "开始时间，毫秒时间戳"),
+        ("queue_id", "队列ID，如 420 为单双排"),
+        ("game_mode", "游戏模式，如 CLASSIC、ARAM"),
+        ("duration", "对局时长，秒，短于 300 秒通常为重开"),
+        ("puuid", "玩家 puuid"),
+        ("champion_id", "英雄ID"),
+        (
+            "position",
+            "位置 top/jungle/middle/bottom/utility，未知时为空",
+        ),
+        ("win", "是否胜利"),
+        ("kills", "击杀"),
+        ("deaths", "死亡"),
+        ("assists", "助攻"),
+        ("damage", "对英雄造成的伤害"),
+        ("gold", "获得的金币"),
+        ("cs", "补刀，包括野怪"),
+        ("vision", "视野得分"),
+    ];
 }
 
-#[derive(Debug)]
+#[derive(Debug, Default)]
 pub struct Matches(Vec<Match>);
-pub const MAX_MATCHES: usize = 20;
 
 impl Matches {
-    pub fn calculate_player_score(self, game_mode: &str) -> PlayerScore {
+    /// 指定时间之后开始的对局ID和玩家所在的队伍
+    pub(crate) fn teams_since(&self, since: i64) -> impl Iterator<Item = (u64, u16)> {
+        self.0
+            .iter()
+            .filter(move |m| m.game_id != 0 && m.game_creation >= since)
+            .map(|m| (m.game_id, m.player().team_id))
+    }
+
+    pub fn calculate_player_score(self, filter: &MatchFilter) -> PlayerScore {
+        let scanned = self.0.len() as u32;
         let matches = self
             .0
             .into_iter()
-            .filter(|m| m.game_mode == game_mode)
+            .filter(|m| filter.matches(m))
             .collect::<Vec<Match>>();
 
         let total = matches.len() as u32;
         if total == 0 {
-            return PlayerScore::default();
+            return PlayerScore {
+                scanned,
+                ..Default::default()
+            };
         }
         let (mut kills, mut deaths, mut assists, mut wins, mut damage, mut duration) =
             (0, 0, 0, 0, 0, 0);
+        let (mut cs, mut gold, mut vision) = (0, 0, 0);
+        // 有队伍数据的对局中的击杀+助攻、队伍击杀、伤害、队伍伤害
+        let (mut takedowns, mut team_kills, mut team_damage_dealt, mut team_damage) = (0, 0, 0, 0);
+        let mut champions = HashMap::<u16, ChampionUsage>::new();
+        let mut roles = BTreeMap::<Position, u32>::new();
         for m in &matches {
-            kills += m.status.kills;
-            deaths += m.status.deaths;
-            assists += m.status.assists;
-            wins += m.status.win as u32;
-            damage += m.status.total_damage_dealt_to_champions;
+            let player = m.player();
+            let status = &player.stats;
+            kills += status.kills;
+            deaths += status.deaths;
+            assists += status.assists;
+            wins += status.win as u32;
+            damage += status.total_damage_dealt_to_champions;
             duration += m.game_duration;
+            cs += status.total_minions_killed + status.neutral_minions_killed;
+            gold += status.gold_earned;
+            vision += status.vision_score;
+            if let Some((kills, damage)) = m.team_totals() {
+                takedowns += (status.kills + status.assists) as u32;
+                team_kills += kills;
+                team_damage_dealt += status.total_damage_dealt_to_champions;
+                team_damage += damage;
+            }
+            let usage = champions
+                .entry(player.champion_id)
+                .or_insert_with(|| ChampionUsage {
+                    champion_id: player.champion_id,
+                    ..Default::default()
+                });
+            usage.games += 1;
+            usage.wins += status.win as u32;
+            if let Some(position) = player.timeline.position() {
+                *roles.entry(position).or_default() += 1;
+            }
         }
 
-        let (win, mut repeat) = (matches[0].status.win, 1);
+        let (win, mut repeat) = (matches[0].player().stats.win, 1);
         for m in &matches[1..] {
-            if m.status.win != win {
+            if m.player().stats.win != win {
                 break;
             }
             repeat += 1;
         }
 
+        let per_minute = |value: u32| {
+            if duration > 0 {
+                value as f32 / duration as f32 * 60.0
+            } else {
+                0.0
+            }
+        };
+        let percent =
+            |value: u32, total: u32| (total > 0).then(|| value as f32 / total as f32 * 100.0);
+        let mut champions = champions.into_values().collect::<Vec<_>>();
+        champions.sort_by(|a, b| b.games.cmp(&a.games).then(b.wins.cmp(&a.wins)));
+        let mut roles = roles.into_iter().collect::<Vec<_>>();
+        roles.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)));
+
         PlayerScore {
+            puuid: String::new(),
             name: String::new(),
             kda: (kills + assists) as f32 / (deaths + 1) as f32,
-            dpm: if duration > 0 {
-                (damage as f32 / duration as f32) * 60.0 // 分均伤害
-            } else {
-                0.0
-            },
+            dpm: per_minute(damage), // 分均伤害
             repeats: if win { repeat } else { -repeat },
             wins,
             total,
+            cs_per_min: per_minute(cs),
+            gpm: per_minute(gold),
+            vision: vision as f32 / total as f32,
+            kill_participation: percent(takedowns, team_kills),
+            damage_share: percent(team_damage_dealt, team_damage),
+            champions,
+            roles,
+            avg_duration: duration / total,
+            scanned,
+            ranked: None,
+            premade: None,
+            encounters: None,
         }
     }
 }
@@ -136,45 +566,326 @@ impl<'de> Deserialize<'de> for Matches {
     }
 }
 
-fn deserialize_status_from_participants<'de, D>(
+fn deserialize_participants<'de, D>(
     deserializer: D,
-) -> std::result::Result<Status, D::Error>
+) -> std::result::Result<Vec<Participant>, D::Error>
 where
     D: Deserializer<'de>,
 {
-    #[derive(Deserialize)]
-    struct Participant {
-        stats: Status,
+    let participants: Vec<Participant> = Vec::deserialize(deserializer)?;
+    if participants.is_empty() {
+        return Err(D::Error::custom("No participants found"));
     }
+    Ok(participants)
+}
 
-    let participants: Vec<Participant> = Vec::deserialize(deserializer)?;
-    participants
-        .into_iter()
-        .next()
-        .map(|p| p.stats)
-        .ok_or_else(|| D::Error::custom("No participants found"))
+/// 分页查询对局，直到找到 games 场符合条件的对局或达到查询上限。
+/// 查询到本地数据库中连续保存的范围内的对局后，范围内更早的对局从数据库读取，
+/// 数据库中的对局不够时从范围之后的位置继续分页查询
+async fn collect_matches<F, Fut>(
+    store: &MatchStore,
+    puuid: &str,
+    filter: &MatchFilter,
+    games: usize,
+    mut fetch_page: F,
+) -> Result<Matches>
+where
+    F: FnMut(usize) -> Fut,
+    Fut: Future<Output = Result<Matches>>,
+{
+    let range = store
+        .synced_range(puuid)
+        .inspect_err(|e| warn!("读取本地对局失败: {e}"))
+        .ok()
+        .flatten();
+    let mut collected = Matches::default();
+    let mut found = 0;
+    let mut reached_stored = false;
+    let mut begin = 0;
+    let mut last_page = false;
+    while found < games && begin < MAX_SCANNED_MATCHES && !last_page {
+        let page = fetch_page(begin).await?;
+        let page_begin = begin;
+        begin += page.0.len();
+        last_page = page.0.len() < PAGE_SIZE;
+        for (index, m) in (page_begin..).zip(page.0) {
+            if found == games {
+                break;
+            }
+            // 跳过已从数据库读取过的对局
+            if collected
+                .0
+                .last()
+                .is_some_and(|last| m.game_creation >= last.game_creation)
+            {
+                continue;
+            }
+            if let Some((_, oldest)) = range.filter(|&(newest, oldest)| {
+                !reached_stored && (oldest..=newest).contains(&m.game_creation)
+            }) {
+                reached_stored = true;
+                let limit = MAX_SCANNED_MATCHES.saturating_sub(index);
+                let stored = store.load_matches(puuid, oldest..=m.game_creation, limit)?;
+                debug!("从本地数据库读取{}场对局", stored.len());
+                // 数据库中的对局不够时，从范围内最早一局之后的位置继续查询
+                begin = index + stored.len();
+                for data in stored {
+                    if found == games {
+                        break;
+                    }
+                    let m = serde_json::from_str::<Match>(&data)?;
+                    found += filter.matches(&m) as usize;
+                    collected.0.push(m);
+                }
+                last_page = false;
+                break;
+            }
+            found += filter.matches(&m) as usize;
+            collected.0.push(m);
+        }
+    }
+    // 收集到的对局从最新一局开始连续，与数据库中的范围相接时合并
+    if let (Some(first), Some(last)) = (collected.0.first(), collected.0.last()) {
+        let oldest = match range {
+            Some((_, oldest)) if reached_stored => oldest.min(last.game_creation),
+            _ => last.game_creation,
+        };
+        if let Err(e) = store.set_synced_range(puuid, first.game_creation, oldest) {
+            warn!("保存对局范围失败: {e}");
+        }
+    }
+    Ok(collected)
 }
 
 impl LcuClient {
-    async fn get_matches(&self, puuid: &str, begin: usize, num: usize) -> Result<Matches> {
+    pub(crate) async fn get_matches(
+        &self,
+        puuid: &str,
+        begin: usize,
+        num: usize,
+    ) -> Result<Matches> {
         let end = begin + num - 1;
         let response = self
             .get(&format!(
                 "/lol-match-history/v1/products/lol/{puuid}/matches?begIndex={begin}&endIndex={end}"
             ))
             .await?;
-        Ok(response.json::<Matches>().await?)
+        let value = response.json::<serde_json::Value>().await?;
+        let matches = Matches::deserialize(&value)?;
+        // 查询到的对局保存到本地数据库，下次分析时不再重复查询
+        let records = matches
+            .0
+            .iter()
+            .zip(value["games"]["games"].as_array().into_iter().flatten())
+            .filter(|(m, _)| m.game_id != 0)
+            .map(|(m, data)| m.record(data.to_string()))
+            .collect::<Vec<_>>();
+        if let Err(e) = STORE.insert_matches(puuid, &records) {
+            warn!("保存对局失败: {e}");
+        }
+        Ok(matches)
+    }
+
+    async fn collect_matches(
+        &self,
+        puuid: &str,
+        filter: &MatchFilter,
+        games: usize,
+    ) -> Result<Matches> {
+        collect_matches(&STORE, puuid, filter, games, |begin| {
+            self.get_matches(puuid, begin, PAGE_SIZE)
+        })
+        .await
+    }
+
+    /// 分页查询玩家最近的 count 场对局，指定队列时只保留该队列的对局，
+    /// 重开的对局也会保留，最多查询 MAX_EXPORTED_MATCHES 场
+    pub async fn match_history(
+        &self,
+        puuid: &str,
+        queue_id: Option<i32>,
+        count: usize,
+    ) -> Result<Vec<MatchSummary>> {
+        let mut summaries = vec![];
+        let mut scanned = 0;
+        while summaries.len() < count && scanned < MAX_EXPORTED_MATCHES {
+            let page = self.get_matches(puuid, scanned, PAGE_SIZE).await?;
+            scanned += page.0.len();
+            let remaining = count - summaries.len();
+            summaries.extend(
+                page.0
+                    .iter()
+                    .filter(|m| queue_id.is_none_or(|queue_id| m.queue_id == queue_id))
+                    .take(remaining)
+                    .map(|m| m.summary(puuid)),
+            );
+            if page.0.len() < PAGE_SIZE {
+                break;
+            }
+        }
+        debug!("导出{}场对局（共查询{}场）", summaries.len(), scanned);
+        Ok(summaries)
     }
 
-    pub async fn analyze_player(&self, puuid: &str, game_mode: &str) -> Result<PlayerScore> {
+    pub async fn analyze_player(&self, puuid: &str, filter: &MatchFilter) -> Result<PlayerScore> {
         let summoner = self
             .get(&format!("/lol-summoner/v2/summoners/puuid/{puuid}"))
             .await?
             .json::<Summoner>()
             .await?;
-        let matches = self.get_matches(puuid, 0, MAX_MATCHES).await?;
-        let mut score = matches.calculate_player_score(game_mode);
+        let games = CONTEXT.analysis_games.load(Ordering::Relaxed) as usize;
+        let matches = self.collect_matches(puuid, filter, games).await?;
+        let mut score = matches.calculate_player_score(filter);
         score.set_name(&summoner.game_name);
+        score.puuid = puuid.to_string();
+        info!(
+            "{}的战绩分析使用了{}场对局（共查询{}场）",
+            score.name, score.total, score.scanned
+        );
+        // 英雄名称查询失败时显示英雄ID，下次分析时再次查询
+        if CONTEXT.champion_names.read().unwrap().is_empty() {
+            match self.get_champion_names().await {
+                Ok(names) => *CONTEXT.champion_names.write().unwrap() = names,
+                Err(e) => warn!("查询英雄名称失败: {e}"),
+            }
+        }
+        score.set_champion_names(&CONTEXT.champion_names.read().unwrap());
+        score.ranked = self
+            .get_ranked_stats(puuid)
+            .await
+            .inspect_err(|e| warn!("查询段位失败: {e}"))
+            .ok();
         Ok(score)
     }
 }
+
+#[test]
+fn test_calculate_player_score() {
+    let player = |team_id: u16, champion_id: u16, kills: u16, win: bool, lane: &str| {
+        format!(
+            r#"{{
+                "teamId": {team_id},
+                "championId": {champion_id},
+                "stats": {{
+                    "assists": 5, "deaths": 2, "kills": {kills}, "win": {win},
+                    "totalDamageDealtToChampions": 12000, "totalMinionsKilled": 150,
+                    "neutralMinionsKilled": 30, "goldEarned": 12000, "visionScore": 20
+                }},
+                "timeline": {{"lane": "{lane}", "role": "SOLO"}}
+            }}"#
+        )
+    };
+    let json = format!(
+        r#"{{"games": {{"games": [
+            {{"gameMode": "CLASSIC", "queueId": 420, "gameDuration": 1800, "participants": [{}, {}, {}]}},
+            {{"gameMode": "CLASSIC", "gameDuration": 1200, "participants": [{}]}},
+            {{"gameMode": "ARAM", "gameDuration": 900, "participants": [{}]}},
+            {{"gameMode": "CLASSIC", "gameDuration": 200, "participants": [{}]}}
+        ]}}}}"#,
+        player(100, 103, 5, true, "MIDDLE"),
+        player(100, 64, 10, true, "JUNGLE"),
+        player(200, 22, 3, false, "BOTTOM"),
+        player(100, 103, 5, false, "TOP"),
+        player(100, 22, 5, false, "NONE"),
+        player(100, 22, 0, false, "NONE"),
+    );
+    let matches: Matches = serde_json::from_str(&json).expect("Deserialization failed");
+    let filter = MatchFilter {
+        queue_id: 0,
+        game_mode: "CLASSIC".to_string(),
+    };
+    let score = matches.calculate_player_score(&filter);
+
+    // 重开的对局不计入
+    assert_eq!((score.wins, score.total, score.scanned), (1, 2, 4));
+    assert_eq!(score.repeats, 1);
+    assert_eq!((score.cs_per_min * 10.0).round(), 72.0);
+    assert_eq!(score.gpm.round(), 480.0);
+    assert_eq!(score.avg_duration, 1500);
+    // 只统计有队友数据的第一场: (5 + 5) / (5 + 10)，伤害各12000
+    assert_eq!(score.kill_participation.map(|kp| kp.round()), Some(67.0));
+    assert_eq!(score.damage_share, Some(50.0));
+    assert_eq!(score.champions.len(), 1);
+    assert_eq!(score.champions[0].games, 2);
+    assert_eq!(score.roles, vec![(Position::Top, 1), (Position::Middle, 1)]);
+
+    // 指定队列时不再按游戏模式筛选
+    let matches: Matches = serde_json::from_str(&json).expect("Deserialization failed");
+    let filter = MatchFilter {
+        queue_id: 420,
+        game_mode: "CLASSIC".to_string(),
+    };
+    assert_eq!(matches.calculate_player_score(&filter).total, 1);
+
+    // 导出的列与 COLUMNS 的顺序一致
+    let matches: Matches = serde_json::from_str(&json).expect("Deserialization failed");
+    let summary = matches.0[0].summary("me");
+    assert_eq!(
+        (summary.position.as_str(), summary.cs, summary.win),
+        ("middle", 180, true)
+    );
+    assert_eq!(matches.0[2].summary("me").position, "");
+    let value = serde_json::to_value(&summary).expect("Serialization failed");
+    let columns = value.as_object().unwrap().keys().collect::<Vec<_>>();
+    assert_eq!(columns.len(), MatchSummary::COLUMNS.len());
+    assert!(
+        MatchSummary::COLUMNS
+            .iter()
+            .all(|(name, _)| value.get(name).is_some())
+    );
+}
+
+#[tokio::test]
+async fn test_collect_matches() {
+    use crate::fixtures;
+    use serde_json::Value;
+
+    // 第 n 局的ID为 n，越靠后开始得越晚
+    let game = |n: usize| -> Value {
+        fixtures::load(fixtures::MATCH, |game| {
+            game["gameId"] = n.into();
+            game["gameCreation"] = (n * 1000).into();
+        })
+    };
+    let store = MatchStore::open_in_memory().unwrap();
+    // 已经进行了 played 局时的战绩列表，和 get_matches 一样保存查询到的对局
+    let page = |played: usize, begin: usize| -> Result<Matches> {
+        let games = (begin..(begin + PAGE_SIZE).min(played))
+            .map(|i| game(played - i))
+            .collect::<Vec<_>>();
+        let records = games
+            .iter()
+            .map(|data| {
+                serde_json::from_value::<Match>(data.clone()).map(|m| m.record(data.to_string()))
+            })
+            .collect::<serde_json::Result<Vec<_>>>()?;
+        store.insert_matches("a", &records)?;
+        Ok(serde_json::from_value(fixtures::match_page(games))?)
+    };
+    let filter = MatchFilter {
+        queue_id: 420,
+        game_mode: String::new(),
+    };
+    let collect = async |played: usize, games: usize| {
+        let mut requested = vec![];
+        let matches = collect_matches(&store, "a", &filter, games, |begin| {
+            requested.push(begin);
+            std::future::ready(page(played, begin))
+        })
+        .await
+        .unwrap();
+        let ids = matches.0.iter().map(|m| m.game_id).collect::<Vec<_>>();
+        (requested, ids)
+    };
+    let ids = |newest: u64, oldest: u64| (oldest..=newest).rev().collect::<Vec<_>>();
+
+    // 保存了第 41-60 局，连续范围为第 51-60 局
+    assert_eq!(collect(60, 10).await, (vec![0], ids(60, 51)));
+    // 又进行了 30 局，保存了第 71-90 局，第 61-70 局没有保存
+    assert_eq!(collect(90, 20).await, (vec![0], ids(90, 71)));
+    // 只读取连续范围内的对局，之后从战绩列表中的实际位置继续查询
+    assert_eq!(collect(90, 40).await, (vec![0, 20], ids(90, 51)));
+    // 新的对局与连续范围相接，不再重复查询
+    assert_eq!(collect(95, 45).await, (vec![0], ids(95, 51)));
+    assert_eq!(store.synced_range("a").unwrap(), Some((95_000, 51_000)));
+}
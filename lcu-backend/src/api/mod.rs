@@ -1,6 +1,12 @@
 mod champ_select;
 mod champions;
 mod chat;
-mod match_history;
+pub(crate) mod encounters;
+mod game_flow;
+pub(crate) mod item_sets;
+pub(crate) mod match_history;
 mod matchmaking;
+pub(crate) mod perks;
+mod premade;
+pub(crate) mod ranked;
 mod summoner;
@@ -0,0 +1,157 @@
+use log::info;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    LcuClient, LcuError, Result,
+    context::LoadoutTarget,
+    library::{self, MANAGED_PREFIX, RUNES},
+};
+
+const PAGES_API: &str = "/lol-perks/v1/pages";
+
+/// 客户端中的符文页
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PerkPage {
+    #[serde(default, skip_serializing)]
+    pub id: u64,
+    pub name: String,
+    pub primary_style_id: u32,
+    pub sub_style_id: u32,
+    pub selected_perk_ids: Vec<u32>,
+    #[serde(default)]
+    pub current: bool,
+    #[serde(default, skip_serializing)]
+    pub is_deletable: bool,
+}
+
+impl PerkPage {
+    pub fn is_managed(&self) -> bool {
+        self.is_deletable && self.name.starts_with(MANAGED_PREFIX)
+    }
+}
+
+/// 符文库中的符文页，英雄、模式和位置用于锁定英雄后选择符文页
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RunePage {
+    pub name: String,
+    #[serde(flatten)]
+    pub target: LoadoutTarget,
+    pub primary_style_id: u32,
+    pub sub_style_id: u32,
+    pub selected_perk_ids: Vec<u32>,
+}
+
+impl RunePage {
+    fn to_perk_page(&self, name: String) -> PerkPage {
+        PerkPage {
+            id: 0,
+            name,
+            primary_style_id: self.primary_style_id,
+            sub_style_id: self.sub_style_id,
+            selected_perk_ids: self.selected_perk_ids.clone(),
+            current: true,
+            is_deletable: true,
+        }
+    }
+}
+
+impl From<&PerkPage> for RunePage {
+    fn from(page: &PerkPage) -> Self {
+        RunePage {
+            name: page.name.clone(),
+            target: LoadoutTarget::default(),
+            primary_style_id: page.primary_style_id,
+            sub_style_id: page.sub_style_id,
+            selected_perk_ids: page.selected_perk_ids.clone(),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct PerkInventory {
+    owned_page_count: usize,
+}
+
+impl LcuClient {
+    pub async fn get_rune_pages(&self) -> Result<Vec<PerkPage>> {
+        Ok(self.get(PAGES_API).await?.json::<Vec<PerkPage>>().await?)
+    }
+
+    /// 创建符文页并设为当前页，符文页数量已满时返回错误
+    async fn create_rune_page(&self, page: &PerkPage) -> Result<()> {
+        let created = self
+            .post_json(PAGES_API, page)
+            .await?
+            .json::<PerkPage>()
+            .await?;
+        self.put_json("/lol-perks/v1/currentpage", &created.id)
+            .await?;
+        Ok(())
+    }
+
+    /// 写入助手管理的符文页，已有管理的符文页时复用该页，避免超过符文页数量上限
+    pub async fn set_managed_rune_page(&self, page: &RunePage) -> Result<()> {
+        let pages = self.get_rune_pages().await?;
+        if let Some(managed) = pages.iter().find(|p| p.is_managed()) {
+            self.delete(&format!("{PAGES_API}/{}", managed.id)).await?;
+        } else {
+            let inventory = self
+                .get("/lol-perks/v1/inventory")
+                .await?
+                .json::<PerkInventory>()
+                .await?;
+            if pages.iter().filter(|p| p.is_deletable).count() >= inventory.owned_page_count {
+                return Err(LcuError::ResponseError(
+                    "符文页数量已达上限，请删除一个符文页供助手使用".to_string(),
+                ));
+            }
+        }
+        let name = format!("{MANAGED_PREFIX} {}", page.name);
+        self.create_rune_page(&page.to_perk_page(name)).await
+    }
+
+    /// 将客户端中的自定义符文页导入符文库，跳过助手管理的符文页
+    pub async fn import_rune_pages(&self) -> Result<usize> {
+        let mut count = 0;
+        for page in self.get_rune_pages().await? {
+            if !page.is_deletable || page.is_managed() {
+                continue;
+            }
+            let path = library::save(RUNES, &page.name, &RunePage::from(&page))?;
+            info!("已导入符文页: {} -> {}", page.name, path.display());
+            count += 1;
+        }
+        Ok(count)
+    }
+
+    /// 将符文库中的符文页导出到客户端
+    pub async fn export_rune_page(&self, name: &str) -> Result<()> {
+        let page = library::load::<RunePage>(RUNES)
+            .into_iter()
+            .find(|page| page.name == name)
+            .ok_or_else(|| LcuError::LibraryError(format!("符文库中没有符文页: {name}")))?;
+        self.create_rune_page(&page.to_perk_page(page.name.clone()))
+            .await
+    }
+}
+
+#[test]
+fn test_rune_page_deserializer() {
+    let page: RunePage = toml::from_str(
+        r#"
+        name = "阿狸-中单"
+        champion_id = 103
+        position = "middle"
+        primary_style_id = 8100
+        sub_style_id = 8200
+        selected_perk_ids = [8112, 8139, 8138, 8135, 8210, 8237, 5008, 5008, 5002]
+        "#,
+    )
+    .unwrap();
+    assert_eq!(page.target.champion_id, Some(103));
+    assert_eq!(page.target.position, Some(crate::Position::Middle));
+    assert_eq!(page.target.game_mode, None);
+    assert_eq!(page.selected_perk_ids.len(), 9);
+}
@@ -0,0 +1,129 @@
+use std::collections::HashMap;
+
+use futures_util::{StreamExt, stream};
+use log::{info, warn};
+
+use crate::{LcuClient, api::match_history::Matches};
+
+// 每位玩家查询的近期对局数，限制请求数量
+const PREMADE_GAMES: usize = 10;
+// 只统计该时间内的对局，毫秒
+const PREMADE_WINDOW: i64 = 24 * 60 * 60 * 1000;
+// 至少共同在同一队伍中的对局数
+const PREMADE_MIN_SHARED: usize = 2;
+// 同时查询的玩家数
+const PREMADE_CONCURRENCY: usize = 2;
+
+/// 根据近期对局找出一起排队的玩家，返回 puuid -> 组队编号，编号从1开始，单排的玩家不包含在内
+pub(crate) fn premade_groups(histories: &[(String, Matches)], since: i64) -> HashMap<String, u8> {
+    // (对局ID, 队伍) -> 在该队伍中的玩家序号
+    let mut teams = HashMap::<(u64, u16), Vec<usize>>::new();
+    for (index, (_, matches)) in histories.iter().enumerate() {
+        for team in matches.teams_since(since) {
+            teams.entry(team).or_default().push(index);
+        }
+    }
+    let mut shared = HashMap::<(usize, usize), usize>::new();
+    for players in teams.values() {
+        for (i, a) in players.iter().enumerate() {
+            for b in &players[i + 1..] {
+                *shared.entry((*a, *b)).or_default() += 1;
+            }
+        }
+    }
+
+    // 合并共同对局足够多的玩家
+    let mut group = (0..histories.len()).collect::<Vec<_>>();
+    fn root(group: &mut [usize], i: usize) -> usize {
+        let mut i = i;
+        while group[i] != i {
+            group[i] = group[group[i]];
+            i = group[i];
+        }
+        i
+    }
+    for (&(a, b), &count) in &shared {
+        if count >= PREMADE_MIN_SHARED {
+            let (a, b) = (root(&mut group, a), root(&mut group, b));
+            group[a.max(b)] = a.min(b);
+        }
+    }
+
+    let mut members = HashMap::<usize, Vec<usize>>::new();
+    for i in 0..histories.len() {
+        let root = root(&mut group, i);
+        members.entry(root).or_default().push(i);
+    }
+    let mut groups = members
+        .into_values()
+        .filter(|members| members.len() > 1)
+        .collect::<Vec<_>>();
+    groups.sort();
+    groups
+        .iter()
+        .enumerate()
+        .flat_map(|(number, members)| {
+            members
+                .iter()
+                .map(move |&i| (histories[i].0.clone(), number as u8 + 1))
+        })
+        .collect()
+}
+
+impl LcuClient {
+    /// 查询玩家的近期对局并检测组队，每位玩家只查询一页战绩
+    pub(crate) async fn detect_premades(&self, puuids: &[String]) -> HashMap<String, u8> {
+        let results = stream::iter(puuids.iter().cloned())
+            .map(|puuid| async move {
+                self.get_matches(&puuid, 0, PREMADE_GAMES)
+                    .await
+                    .map(|matches| (puuid, matches))
+            })
+            .buffered(PREMADE_CONCURRENCY)
+            .collect::<Vec<_>>()
+            .await;
+        let histories = results
+            .into_iter()
+            .filter_map(|result| result.inspect_err(|e| warn!("查询组队信息失败: {e}")).ok())
+            .collect::<Vec<_>>();
+        let since = chrono::Utc::now().timestamp_millis() - PREMADE_WINDOW;
+        let groups = premade_groups(&histories, since);
+        if !groups.is_empty() {
+            info!("检测到{}名组队玩家", groups.len());
+        }
+        groups
+    }
+}
+
+#[test]
+fn test_premade_groups() {
+    use crate::fixtures;
+
+    let history = |games: &[(u64, u16)]| -> Matches {
+        let games = games.iter().map(|(game_id, team_id)| {
+            fixtures::load::<serde_json::Value>(fixtures::MATCH, |game| {
+                game["gameId"] = (*game_id).into();
+                game["gameCreation"] = 1000.into();
+                game["participants"][0]["teamId"] = (*team_id).into();
+            })
+        });
+        serde_json::from_value(fixtures::match_page(games)).expect("Deserialization failed")
+    };
+    let histories = vec![
+        ("a".to_string(), history(&[(1, 100), (2, 100), (3, 200)])),
+        ("b".to_string(), history(&[(1, 100), (2, 100)])),
+        // 只有一局在同一队伍
+        ("c".to_string(), history(&[(3, 200), (2, 200)])),
+        ("d".to_string(), history(&[(4, 100), (5, 100)])),
+        ("e".to_string(), history(&[(4, 100), (5, 100)])),
+    ];
+    let groups = premade_groups(&histories, 0);
+    assert_eq!(groups.len(), 4);
+    assert_eq!(groups["a"], groups["b"]);
+    assert_eq!(groups["d"], groups["e"]);
+    assert_ne!(groups["a"], groups["d"]);
+    assert!(!groups.contains_key("c"));
+
+    // 超出时间范围的对局不统计
+    assert!(premade_groups(&histories, 2000).is_empty());
+}
@@ -0,0 +1,207 @@
+use log::debug;
+use serde::{Deserialize, Deserializer};
+
+use crate::{CONTEXT, LcuClient, Result};
+
+/// 单个排位队列的段位信息
+#[derive(Debug, Default, Clone, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct RankedQueue {
+    pub tier: String, // 未定级时为空或 NONE
+    pub division: String,
+    pub league_points: i32,
+    pub wins: u32,
+    pub losses: u32,
+    pub previous_season_end_tier: String,
+    pub previous_season_end_division: String,
+    // 晋级赛进度，如 "WLN"，N 表示未进行
+    pub mini_series_progress: String,
+}
+
+fn tier_name(tier: &str) -> Option<&'static str> {
+    let name = match tier {
+        "IRON" => "黑铁",
+        "BRONZE" => "青铜",
+        "SILVER" => "白银",
+        "GOLD" => "黄金",
+        "PLATINUM" => "铂金",
+        "EMERALD" => "翡翠",
+        "DIAMOND" => "钻石",
+        "MASTER" => "大师",
+        "GRANDMASTER" => "宗师",
+        "CHALLENGER" => "王者",
+        _ => return None,
+    };
+    Some(name)
+}
+
+/// 段位名称，大师及以上没有小段
+fn rank_name(tier: &str, division: &str) -> Option<String> {
+    let tier = tier_name(tier)?;
+    match division {
+        "I" | "II" | "III" | "IV" => Some(format!("{tier} {division}")),
+        _ => Some(tier.to_string()),
+    }
+}
+
+impl RankedQueue {
+    /// 当前段位和胜点，未定级时为None
+    pub fn rank(&self) -> Option<String> {
+        rank_name(&self.tier, &self.division).map(|rank| format!("{rank} {}LP", self.league_points))
+    }
+
+    pub fn previous_rank(&self) -> Option<String> {
+        rank_name(
+            &self.previous_season_end_tier,
+            &self.previous_season_end_division,
+        )
+    }
+
+    /// 本赛季排位胜率百分比，没有排位场次时为None
+    pub fn win_rate(&self) -> Option<u32> {
+        (self.wins * 100).checked_div(self.wins + self.losses)
+    }
+
+    /// 和之前段位相比的变化，如 "黄金 II 63LP（+18LP）"，当前未定级时为None
+    pub fn change_since(&self, before: &RankedQueue) -> Option<String> {
+        let rank = self.rank()?;
+        if self.tier == before.tier && self.division == before.division {
+            return Some(format!(
+                "{rank}（{:+}LP）",
+                self.league_points - before.league_points
+            ));
+        }
+        let before = before.rank().unwrap_or_else(|| "未定级".to_string());
+        Some(format!("{before} → {rank}"))
+    }
+
+    /// 晋级赛进度，不在晋级赛中时为None
+    pub fn series(&self) -> Option<String> {
+        if self.mini_series_progress.is_empty() {
+            return None;
+        }
+        Some(
+            self.mini_series_progress
+                .chars()
+                .map(|c| match c {
+                    'W' => '胜',
+                    'L' => '负',
+                    _ => '-',
+                })
+                .collect(),
+        )
+    }
+}
+
+/// 单双排和灵活排位的段位信息
+#[derive(Debug, Default, Clone)]
+pub struct RankedStats {
+    pub solo: RankedQueue,
+    pub flex: RankedQueue,
+}
+
+impl RankedStats {
+    /// 根据结算数据中的队列类型选择段位，非排位队列时为None
+    pub fn queue(&self, queue_type: &str) -> Option<&RankedQueue> {
+        match queue_type {
+            "RANKED_SOLO_5x5" => Some(&self.solo),
+            "RANKED_FLEX_SR" => Some(&self.flex),
+            _ => None,
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for RankedStats {
+    fn deserialize<D>(deserializer: D) -> std::result::Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        #[derive(Deserialize)]
+        struct QueueMap {
+            #[serde(rename = "RANKED_SOLO_5x5", default)]
+            solo: RankedQueue,
+            #[serde(rename = "RANKED_FLEX_SR", default)]
+            flex: RankedQueue,
+        }
+
+        #[derive(Deserialize)]
+        #[serde(rename_all = "camelCase")]
+        struct Wrapper {
+            queue_map: QueueMap,
+        }
+
+        let wrapper = Wrapper::deserialize(deserializer)?;
+        Ok(RankedStats {
+            solo: wrapper.queue_map.solo,
+            flex: wrapper.queue_map.flex,
+        })
+    }
+}
+
+impl LcuClient {
+    /// 查询玩家的段位，同一局中每位玩家只查询一次
+    pub async fn get_ranked_stats(&self, puuid: &str) -> Result<RankedStats> {
+        if let Some(stats) = CONTEXT.ranked_cache.read().unwrap().get(puuid) {
+            debug!("使用缓存的段位信息: {puuid}");
+            return Ok(stats.clone());
+        }
+        self.fetch_ranked_stats(puuid).await
+    }
+
+    /// 不使用缓存查询最新的段位，并更新缓存
+    pub(crate) async fn fetch_ranked_stats(&self, puuid: &str) -> Result<RankedStats> {
+        let stats = self
+            .get(&format!("/lol-ranked/v1/ranked-stats/{puuid}"))
+            .await?
+            .json::<RankedStats>()
+            .await?;
+        CONTEXT
+            .ranked_cache
+            .write()
+            .unwrap()
+            .insert(puuid.to_string(), stats.clone());
+        Ok(stats)
+    }
+}
+
+#[test]
+fn test_ranked_stats_deserializer() {
+    let json = r#"{
+        "queueMap": {
+            "RANKED_SOLO_5x5": {
+                "tier": "GOLD", "division": "II", "leaguePoints": 45, "wins": 30, "losses": 20,
+                "previousSeasonEndTier": "SILVER", "previousSeasonEndDivision": "I",
+                "miniSeriesProgress": ""
+            },
+            "RANKED_FLEX_SR": {
+                "tier": "MASTER", "division": "NA", "leaguePoints": 120, "wins": 0, "losses": 0,
+                "previousSeasonEndTier": "", "previousSeasonEndDivision": "NA",
+                "miniSeriesProgress": "WLN"
+            },
+            "RANKED_TFT": {"tier": "IRON"}
+        }
+    }"#;
+    let stats: RankedStats = serde_json::from_str(json).expect("Deserialization failed");
+    assert_eq!(stats.solo.rank().as_deref(), Some("黄金 II 45LP"));
+    assert_eq!(stats.solo.previous_rank().as_deref(), Some("白银 I"));
+    assert_eq!(stats.solo.win_rate(), Some(60));
+    assert_eq!(stats.solo.series(), None);
+    assert_eq!(stats.flex.rank().as_deref(), Some("大师 120LP"));
+    assert_eq!(stats.flex.previous_rank(), None);
+    assert_eq!(stats.flex.win_rate(), None);
+    assert_eq!(stats.flex.series().as_deref(), Some("胜负-"));
+
+    let mut after = stats.solo.clone();
+    after.league_points = 63;
+    assert_eq!(
+        after.change_since(&stats.solo).as_deref(),
+        Some("黄金 II 63LP（+18LP）")
+    );
+    after.division = "I".to_string();
+    assert_eq!(
+        after.change_since(&stats.solo).as_deref(),
+        Some("黄金 II 45LP → 黄金 I 63LP")
+    );
+    assert!(stats.queue("RANKED_FLEX_SR").is_some());
+    assert!(stats.queue("NORMAL").is_none());
+}
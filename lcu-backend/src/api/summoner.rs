@@ -18,4 +18,16 @@ impl LcuClient {
         *CONTEXT.me.write().unwrap() = data;
         Ok(())
     }
+
+    /// 根据 Riot ID（名称#标签）查找玩家
+    pub async fn find_summoner(&self, riot_id: &str) -> Result<Summoner> {
+        Ok(self
+            .get(&format!(
+                "/lol-summoner/v1/summoners?name={}",
+                riot_id.replace('#', "%23")
+            ))
+            .await?
+            .json::<Summoner>()
+            .await?)
+    }
 }
@@ -8,6 +8,7 @@ use log::debug;
 
 use reqwest::Response;
 
+#[derive(Clone)]
 pub struct LcuClient {
     pub client: Arc<reqwest::Client>,
     pub meta: LcuMeta,
@@ -85,6 +86,19 @@ impl LcuClient {
         self.request(reqwest::Method::PATCH, api, Some(body)).await
     }
 
+    pub(crate) async fn put_json<T: serde::Serialize>(
+        &self,
+        api: &str,
+        body: &T,
+    ) -> Result<Response> {
+        self.request(reqwest::Method::PUT, api, Some(body)).await
+    }
+
+    pub(crate) async fn delete(&self, api: &str) -> Result<Response> {
+        self.request(reqwest::Method::DELETE, api, Option::<&()>::None)
+            .await
+    }
+
     pub async fn handle_message(&self, message: String) -> Result<()> {
         if message.is_empty() {
             return Ok(());
@@ -1,8 +1,19 @@
-use crate::GamePhase;
+use crate::events::champ_select_state::ChampSelectState;
+use crate::events::lobby_report::LobbyReport;
+use crate::events::post_game::PostGameReport;
+use crate::output::OutputSettings;
+use crate::store::DEFAULT_RETENTION_DAYS;
+use crate::template::AnalysisTemplates;
+use crate::{
+    DEFAULT_ANALYSIS_GAMES, EncounterIndex, GamePhase, PlayerScore, RankedStats, SkipReason,
+};
 use log::debug;
 use serde::{Deserialize, Serialize};
-use std::sync::atomic::{AtomicBool, AtomicU8, AtomicU16, Ordering};
-use std::sync::{LazyLock, RwLock};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::str::FromStr;
+use std::sync::atomic::{AtomicBool, AtomicI32, AtomicU8, AtomicU16, AtomicU64, Ordering};
+use std::sync::{LazyLock, Mutex, RwLock};
+use std::time::Instant;
 
 pub static CONTEXT: LazyLock<HelperContext> = LazyLock::new(HelperContext::new);
 
@@ -21,11 +32,477 @@ pub struct Summoner {
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Champion(pub u16, pub String);
 
-#[derive(Debug, Default, Serialize, Deserialize)]
+// 英雄选择阶段分配的位置，对应 assignedPosition
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Position {
+    Top,
+    Jungle,
+    Middle,
+    Bottom,
+    Utility,
+}
+
+impl Position {
+    pub const ALL: [Position; 5] = [
+        Position::Top,
+        Position::Jungle,
+        Position::Middle,
+        Position::Bottom,
+        Position::Utility,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Position::Top => "上单",
+            Position::Jungle => "打野",
+            Position::Middle => "中单",
+            Position::Bottom => "下路",
+            Position::Utility => "辅助",
+        }
+    }
+
+    /// 英文标识，与 assignedPosition 中的值一致
+    pub fn key(self) -> &'static str {
+        match self {
+            Position::Top => "top",
+            Position::Jungle => "jungle",
+            Position::Middle => "middle",
+            Position::Bottom => "bottom",
+            Position::Utility => "utility",
+        }
+    }
+}
+
+impl FromStr for Position {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "top" | "上单" => Ok(Position::Top),
+            "jungle" | "jug" | "打野" => Ok(Position::Jungle),
+            "middle" | "mid" | "中单" => Ok(Position::Middle),
+            "bottom" | "bot" | "adc" | "下路" => Ok(Position::Bottom),
+            "utility" | "support" | "sup" | "辅助" => Ok(Position::Utility),
+            _ => Err(format!("Unknown position: {s}")),
+        }
+    }
+}
+
+// 选择英雄后的锁定行为，等待的秒数由 AutoPick.lock_seconds 指定
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum LockMode {
+    // 立即锁定
+    #[default]
+    Immediately,
+    // 预选后等待N秒锁定
+    AfterDelay,
+    // 预选后在选择阶段剩余N秒时锁定
+    LastSeconds,
+    // 仅预选，不锁定
+    HoverOnly,
+}
+
+impl LockMode {
+    pub const ALL: [LockMode; 4] = [
+        LockMode::Immediately,
+        LockMode::AfterDelay,
+        LockMode::LastSeconds,
+        LockMode::HoverOnly,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            LockMode::Immediately => "立即锁定",
+            LockMode::AfterDelay => "延迟锁定",
+            LockMode::LastSeconds => "最后几秒锁定",
+            LockMode::HoverOnly => "仅预选",
+        }
+    }
+}
+
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
 pub struct AutoPick {
+    // 通用列表，分配位置没有单独配置时使用
+    pub selected: Vec<Champion>,
+    #[serde(default)]
+    pub positions: BTreeMap<Position, Vec<Champion>>,
+    pub enabled: bool,
+    #[serde(default)]
+    pub lock_mode: LockMode,
+    #[serde(default)]
+    pub lock_seconds: u8,
+    // 计划阶段预选英雄，向队友声明意向
+    #[serde(default)]
+    pub declare_intent: bool,
+    #[serde(default)]
+    pub bench: BenchSnipe,
+    // 位置偏好，靠前的位置优先，处理交换位置请求时使用
+    #[serde(default)]
+    pub position_preference: Vec<Position>,
+}
+
+/// 大乱斗备选席抢英雄设置，优先级使用通用列表
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BenchSnipe {
+    // 当前英雄不在列表前N位时使用重随，0表示不重随
+    pub reroll_below: u8,
+    // 两次交换或重随之间的最短间隔
+    pub cooldown_seconds: u8,
+}
+
+impl Default for BenchSnipe {
+    fn default() -> Self {
+        Self {
+            reroll_below: 0,
+            cooldown_seconds: 3,
+        }
+    }
+}
+
+impl AutoPick {
+    /// 根据分配的位置获取候选英雄，位置列表中的英雄优先，之后是通用列表中的其他英雄
+    pub fn candidates(&self, position: Option<Position>) -> Vec<Champion> {
+        let mut candidates = position
+            .and_then(|position| self.positions.get(&position))
+            .cloned()
+            .unwrap_or_default();
+        for champion in &self.selected {
+            if !candidates.iter().any(|candidate| candidate.0 == champion.0) {
+                candidates.push(champion.clone());
+            }
+        }
+        candidates
+    }
+}
+
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct AutoBan {
     pub selected: Vec<Champion>,
-    pub unselected: Vec<Champion>,
     pub enabled: bool,
+    // 禁用阶段剩余秒数小于该值时才禁用，0表示立即禁用
+    pub wait_seconds: u8,
+}
+
+// 收到交换请求时的处理方式
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SwapRule {
+    // 不处理，由玩家手动决定
+    #[default]
+    Ignore,
+    Accept,
+    Decline,
+    // 根据选择列表判断是否有利
+    Smart,
+}
+
+impl SwapRule {
+    pub const ALL: [SwapRule; 4] = [
+        SwapRule::Ignore,
+        SwapRule::Accept,
+        SwapRule::Decline,
+        SwapRule::Smart,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            SwapRule::Ignore => "不处理",
+            SwapRule::Accept => "总是接受",
+            SwapRule::Decline => "总是拒绝",
+            SwapRule::Smart => "智能判断",
+        }
+    }
+}
+
+/// 英雄选择中交换英雄、选择顺序和位置请求的处理方式
+#[derive(Debug, Default, Clone, Copy, Serialize, Deserialize)]
+pub struct SwapRules {
+    // 交换英雄：对方英雄在选择列表中且优先级更高时接受
+    pub trade: SwapRule,
+    // 交换选择顺序：更早选择可以拿到优先级更高的英雄时接受
+    pub pick_order: SwapRule,
+    // 交换位置：对方位置在位置偏好中比当前位置靠前时接受
+    pub position: SwapRule,
+}
+
+// 召唤师技能ID和名称
+pub const SUMMONER_SPELLS: [(u16, &str); 11] = [
+    (4, "闪现"),
+    (14, "引燃"),
+    (11, "惩戒"),
+    (12, "传送"),
+    (6, "幽灵疾步"),
+    (7, "治疗术"),
+    (21, "屏障"),
+    (3, "虚弱"),
+    (1, "净化"),
+    (13, "清晰术"),
+    (32, "标记"),
+];
+
+/// 根据名称或ID查找召唤师技能
+pub fn spell_id(name: &str) -> Option<u16> {
+    name.parse().ok().or_else(|| {
+        SUMMONER_SPELLS
+            .iter()
+            .find(|(_, spell)| *spell == name)
+            .map(|(id, _)| *id)
+    })
+}
+
+pub fn spell_name(id: u16) -> &'static str {
+    SUMMONER_SPELLS
+        .iter()
+        .find(|(spell, _)| *spell == id)
+        .map_or("未知", |(_, name)| name)
+}
+
+/// 预设适用的英雄、模式和位置，为空时匹配任意值
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct LoadoutTarget {
+    #[serde(default)]
+    pub champion_id: Option<u16>,
+    #[serde(default)]
+    pub game_mode: Option<String>,
+    #[serde(default)]
+    pub position: Option<Position>,
+}
+
+impl LoadoutTarget {
+    /// 匹配条件最多的预设，相同时使用靠前的预设
+    pub fn best_match<'a, T>(
+        items: &'a [T],
+        target: impl Fn(&T) -> &LoadoutTarget,
+        champion_id: u16,
+        game_mode: &str,
+        position: Option<Position>,
+    ) -> Option<&'a T> {
+        items
+            .iter()
+            .filter(|item| target(item).matches(champion_id, game_mode, position))
+            .rev()
+            .max_by_key(|item| target(item).specificity())
+    }
+
+    pub fn matches(&self, champion_id: u16, game_mode: &str, position: Option<Position>) -> bool {
+        self.champion_id.is_none_or(|id| id == champion_id)
+            && self
+                .game_mode
+                .as_ref()
+                .is_none_or(|mode| mode.eq_ignore_ascii_case(game_mode))
+            && self.position.is_none_or(|p| Some(p) == position)
+    }
+
+    // 指定的条件越多越优先
+    fn specificity(&self) -> u8 {
+        self.champion_id.is_some() as u8 * 4
+            + self.game_mode.is_some() as u8 * 2
+            + self.position.is_some() as u8
+    }
+}
+
+/// 召唤师技能预设
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SpellPreset {
+    #[serde(flatten)]
+    pub target: LoadoutTarget,
+    // D键技能
+    pub spell1: u16,
+    // F键技能
+    pub spell2: u16,
+}
+
+// 锁定英雄后的皮肤选择方式
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SkinStrategy {
+    // 默认皮肤
+    #[default]
+    Default,
+    // 上次在该英雄上手动选择的皮肤
+    Favorite,
+    // 随机已拥有的皮肤
+    Random,
+    // 随机已拥有的皮肤或炫彩
+    RandomChroma,
+}
+
+impl SkinStrategy {
+    pub const ALL: [SkinStrategy; 4] = [
+        SkinStrategy::Default,
+        SkinStrategy::Favorite,
+        SkinStrategy::Random,
+        SkinStrategy::RandomChroma,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            SkinStrategy::Default => "默认皮肤",
+            SkinStrategy::Favorite => "常用皮肤",
+            SkinStrategy::Random => "随机皮肤",
+            SkinStrategy::RandomChroma => "随机皮肤和炫彩",
+        }
+    }
+}
+
+/// 锁定英雄后自动设置的召唤师技能、皮肤、符文页和出装方案
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct Loadout {
+    pub enabled: bool,
+    pub spells: Vec<SpellPreset>,
+    pub skin_strategy: SkinStrategy,
+    // 英雄ID -> 皮肤ID
+    #[serde(default)]
+    pub favorite_skins: BTreeMap<u16, u32>,
+    // 从本地符文库中选择符文页
+    #[serde(default)]
+    pub runes: bool,
+    // 从本地出装库中写入出装方案
+    #[serde(default)]
+    pub item_sets: bool,
+}
+
+impl Loadout {
+    pub fn spell_preset(
+        &self,
+        champion_id: u16,
+        game_mode: &str,
+        position: Option<Position>,
+    ) -> Option<&SpellPreset> {
+        LoadoutTarget::best_match(
+            &self.spells,
+            |preset| &preset.target,
+            champion_id,
+            game_mode,
+            position,
+        )
+    }
+}
+
+/// 选择/禁用方案，绑定队列ID或游戏模式后在进入对应模式时自动切换
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PickProfile {
+    pub name: String,
+    #[serde(default)]
+    pub queue_ids: Vec<i32>,
+    // CLASSIC、ARAM、URF、CHERRY 等
+    #[serde(default)]
+    pub game_modes: Vec<String>,
+    pub auto_pick: AutoPick,
+    pub auto_ban: AutoBan,
+    #[serde(default)]
+    pub swaps: SwapRules,
+}
+
+impl PickProfile {
+    pub fn new(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            queue_ids: vec![],
+            game_modes: vec![],
+            auto_pick: AutoPick {
+                enabled: true,
+                ..Default::default()
+            },
+            auto_ban: AutoBan::default(),
+            swaps: SwapRules::default(),
+        }
+    }
+}
+
+/// 所有方案，第一个方案为默认方案，没有方案匹配当前模式时使用
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(from = "StoredPickProfiles")]
+pub struct PickProfiles {
+    pub profiles: Vec<PickProfile>,
+    pub active: usize,
+    // 上一次自动切换时的队列和游戏模式，模式不变时保留手动选择的方案
+    #[serde(skip)]
+    last_mode: Option<(i32, String)>,
+}
+
+// 方案列表为空时使用的默认方案
+static DEFAULT_PROFILE: LazyLock<PickProfile> = LazyLock::new(|| PickProfile::new("默认"));
+
+/// 保存的方案，手动修改或损坏的数据中方案列表可能为空
+#[derive(Deserialize)]
+struct StoredPickProfiles {
+    profiles: Vec<PickProfile>,
+    active: usize,
+}
+
+impl From<StoredPickProfiles> for PickProfiles {
+    fn from(stored: StoredPickProfiles) -> Self {
+        if stored.profiles.is_empty() {
+            return Self::default();
+        }
+        let active = if stored.active < stored.profiles.len() {
+            stored.active
+        } else {
+            0
+        };
+        Self {
+            profiles: stored.profiles,
+            active,
+            last_mode: None,
+        }
+    }
+}
+
+impl Default for PickProfiles {
+    fn default() -> Self {
+        Self {
+            profiles: vec![PickProfile::new("默认")],
+            active: 0,
+            last_mode: None,
+        }
+    }
+}
+
+impl PickProfiles {
+    pub fn active(&self) -> &PickProfile {
+        self.profiles
+            .get(self.active)
+            .or(self.profiles.first())
+            .unwrap_or(&DEFAULT_PROFILE)
+    }
+
+    pub fn active_mut(&mut self) -> &mut PickProfile {
+        if self.profiles.is_empty() {
+            self.profiles.push(PickProfile::new("默认"));
+        }
+        if self.active >= self.profiles.len() {
+            self.active = 0;
+        }
+        &mut self.profiles[self.active]
+    }
+
+    /// 根据队列和游戏模式切换方案，队列ID优先于游戏模式，切换后返回新方案名称。
+    /// 队列和游戏模式与上一次相同时不切换，保留手动选择的方案
+    pub fn switch(&mut self, queue_id: i32, game_mode: &str) -> Option<String> {
+        let mode = (queue_id, game_mode.to_uppercase());
+        if self.last_mode.as_ref() == Some(&mode) {
+            return None;
+        }
+        self.last_mode = Some(mode);
+        let index = self
+            .profiles
+            .iter()
+            .position(|profile| profile.queue_ids.contains(&queue_id))
+            .or_else(|| {
+                self.profiles.iter().position(|profile| {
+                    profile
+                        .game_modes
+                        .iter()
+                        .any(|mode| mode.eq_ignore_ascii_case(game_mode))
+                })
+            })
+            .unwrap_or(0);
+        if index == self.active {
+            return None;
+        }
+        self.active = index;
+        self.profiles.get(index).map(|profile| profile.name.clone())
+    }
 }
 
 #[derive(Debug, Default)]
@@ -36,36 +513,172 @@ pub struct HelperContext {
     pub me: RwLock<Summoner>,
     pub game_phase: RwLock<GamePhase>,
     pub game_mode: RwLock<String>,
+    pub queue_id: AtomicI32,
+    // 英雄选择中不可用的英雄及原因
+    pub unavailable_champions: RwLock<HashMap<u16, SkipReason>>,
+
+    // 英雄选择状态机，轮到自己时自动禁用和选择
+    pub champ_select: Mutex<ChampSelectState>,
 
     // flags
-    // For auto pick champion check once
-    pub subset_champion_checked: AtomicBool,
-    pub picked: AtomicBool,
+    pub last_bench_action: Mutex<Option<Instant>>,
+    // 已设置技能和皮肤的英雄
+    pub loadout_champion: AtomicU16,
+    // 已处理的交换请求ID
+    pub handled_swaps: Mutex<HashSet<u64>>,
+    // 本局已提醒过的标记玩家
+    pub alerted_players: Mutex<HashSet<String>>,
+    // 尚未在界面中确认的标记玩家提醒
+    pub player_alerts: RwLock<Vec<String>>,
+    // 英雄选择聊天的ID
+    pub champ_select_conversation: RwLock<Option<String>>,
+    // 本次英雄选择中的队友分析
+    pub team_analysis: RwLock<Vec<PlayerScore>>,
+    // 本次英雄选择中的队友分析任务，离开英雄选择或进入新的英雄选择时取消
+    pub analysis_tasks: Mutex<Vec<tokio::task::AbortHandle>>,
+    // 最近一局的全部玩家分析，进入下一局前保留
+    pub lobby_report: RwLock<Option<LobbyReport>>,
+    // 已生成对局报告的游戏ID
+    pub reported_game: AtomicU64,
+    // 游戏开始时自己的段位，(游戏ID, 段位)
+    pub ranked_before: RwLock<Option<(u64, RankedStats)>>,
+    // 最近一局的赛后报告
+    pub post_game_report: RwLock<Option<PostGameReport>>,
+    // 已生成赛后报告的游戏ID
+    pub post_game_reported: AtomicU64,
     pub accepted: AtomicBool,
+    // 自动重新连接的后台任务，离开重新连接状态时取消
+    pub reconnect_task: Mutex<Option<tokio::task::AbortHandle>>,
     // Settings
-    pub auto_pick: RwLock<AutoPick>,
+    // 已拥有的英雄，作为选择和禁用列表的候选
+    pub champions: RwLock<Vec<Champion>>,
+    // 自己近期对局中遇到过的玩家，每次连接客户端时清空
+    pub encounter_index: RwLock<EncounterIndex>,
+    // puuid -> 段位信息，每次连接客户端、回到房间或对局结束时清空
+    pub ranked_cache: RwLock<HashMap<String, RankedStats>>,
+    // 全部英雄ID -> 名称，战绩分析时按需加载
+    pub champion_names: RwLock<HashMap<u16, String>>,
+    pub profiles: RwLock<PickProfiles>,
+    pub loadout: RwLock<Loadout>,
     pub auto_accepted_delay: AtomicU8,
-    pub auto_send_analysis: AtomicBool,
+    // 各功能分析结果的输出位置
+    pub outputs: RwLock<OutputSettings>,
+    // 将所有队友的分析合并为一条消息发送
+    pub combine_analysis: AtomicBool,
+    pub analysis_templates: RwLock<AnalysisTemplates>,
+    // 赛后报告同时导出为 Markdown 文件
+    pub export_markdown: AtomicBool,
+    // 每位玩家参与分析的对局数
+    pub analysis_games: AtomicU8,
+    // 本地对局数据库保留的天数，0表示永久保留
+    pub retention_days: AtomicU16,
+    // 掉线后自动重新连接
+    pub auto_reconnect: AtomicBool,
 }
 
 impl HelperContext {
     pub fn new() -> Self {
         Self {
             auto_accepted_delay: AtomicU8::new(3),
-            auto_send_analysis: AtomicBool::new(true),
-            auto_pick: RwLock::new(AutoPick {
-                enabled: true,
-                ..Default::default()
-            }),
+            analysis_games: AtomicU8::new(DEFAULT_ANALYSIS_GAMES),
+            retention_days: AtomicU16::new(DEFAULT_RETENTION_DAYS),
+            auto_reconnect: AtomicBool::new(true),
             ..Default::default()
         }
     }
 
     pub fn reset(&self) {
-        self.champion_id.store(0, Ordering::Relaxed);
-        self.picked.store(false, Ordering::Relaxed);
-        self.subset_champion_checked.store(false, Ordering::Relaxed);
+        *self.champ_select.lock().unwrap() = ChampSelectState::default();
+        self.reset_champ_select();
         self.game_mode.write().unwrap().clear();
+        // 段位在每局之后都可能变化
+        self.ranked_cache.write().unwrap().clear();
         debug!("HelperContext reset");
     }
+
+    /// 清除一次英雄选择中记录的状态，状态机由调用方负责重置
+    pub fn reset_champ_select(&self) {
+        for task in self.analysis_tasks.lock().unwrap().drain(..) {
+            task.abort();
+        }
+        self.champion_id.store(0, Ordering::Relaxed);
+        *self.last_bench_action.lock().unwrap() = None;
+        self.loadout_champion.store(0, Ordering::Relaxed);
+        self.handled_swaps.lock().unwrap().clear();
+        self.alerted_players.lock().unwrap().clear();
+        *self.champ_select_conversation.write().unwrap() = None;
+        self.team_analysis.write().unwrap().clear();
+        self.unavailable_champions.write().unwrap().clear();
+    }
+}
+
+#[test]
+fn test_pick_profiles_switch() {
+    let mut profiles = PickProfiles::default();
+    let mut ranked = PickProfile::new("排位");
+    ranked.queue_ids = vec![420, 440];
+    let mut aram = PickProfile::new("大乱斗");
+    aram.game_modes = vec!["ARAM".to_string()];
+    profiles.profiles.extend([ranked, aram]);
+
+    assert_eq!(profiles.switch(420, "CLASSIC").as_deref(), Some("排位"));
+    assert_eq!(profiles.switch(440, "CLASSIC"), None);
+    assert_eq!(profiles.switch(450, "ARAM").as_deref(), Some("大乱斗"));
+    assert_eq!(profiles.switch(430, "CLASSIC").as_deref(), Some("默认"));
+
+    // 模式不变时保留手动选择的方案，模式变化后重新切换
+    profiles.active = 2;
+    assert_eq!(profiles.switch(430, "CLASSIC"), None);
+    assert_eq!(profiles.active().name, "大乱斗");
+    assert_eq!(profiles.switch(420, "CLASSIC").as_deref(), Some("排位"));
+
+    // 保存的方案列表为空或选中的方案不存在时使用默认方案
+    let profiles: PickProfiles =
+        serde_json::from_str(r#"{"profiles": [], "active": 0}"#).expect("Deserialization failed");
+    assert_eq!(profiles.profiles.len(), 1);
+    assert_eq!(profiles.active().name, "默认");
+    let mut profiles = PickProfiles {
+        profiles: vec![],
+        active: 3,
+        last_mode: None,
+    };
+    assert_eq!(profiles.active().name, "默认");
+    assert_eq!(profiles.switch(450, "ARAM"), None);
+    profiles.active_mut().auto_pick.enabled = false;
+    assert_eq!((profiles.profiles.len(), profiles.active), (1, 0));
+}
+
+#[test]
+fn test_loadout_spell_preset() {
+    let loadout = Loadout {
+        spells: vec![
+            SpellPreset {
+                spell1: 14,
+                spell2: 4,
+                ..Default::default()
+            },
+            SpellPreset {
+                target: LoadoutTarget {
+                    game_mode: Some("ARAM".to_string()),
+                    ..Default::default()
+                },
+                spell1: 6,
+                spell2: 4,
+            },
+            SpellPreset {
+                target: LoadoutTarget {
+                    position: Some(Position::Jungle),
+                    ..Default::default()
+                },
+                spell1: 11,
+                spell2: 4,
+            },
+        ],
+        ..Default::default()
+    };
+
+    let spell1 = |mode: &str, position| loadout.spell_preset(1, mode, position).unwrap().spell1;
+    assert_eq!(spell1("CLASSIC", Some(Position::Middle)), 14);
+    assert_eq!(spell1("ARAM", None), 6);
+    assert_eq!(spell1("CLASSIC", Some(Position::Jungle)), 11);
 }
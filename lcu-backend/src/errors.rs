@@ -18,6 +18,12 @@ pub enum LcuError {
     SerializationError(#[from] serde_json::Error),
     #[error("WebSocket error: {0}")]
     WebSocketError(String),
+    #[error("IO error: {0}")]
+    IoError(#[from] std::io::Error),
+    #[error("Library error: {0}")]
+    LibraryError(String),
+    #[error("Database error: {0}")]
+    DatabaseError(#[from] rusqlite::Error),
 }
 
 impl From<reqwest_websocket::Error> for LcuError {
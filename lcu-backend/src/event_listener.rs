@@ -8,7 +8,7 @@ use reqwest_websocket::{CloseCode, Message, RequestBuilderExt};
 use tokio::sync::RwLock;
 
 use super::events::SUBSCRIBED_EVENT;
-use crate::{CONTEXT, LcuClient, Result, default_client};
+use crate::{CONTEXT, LcuClient, Result, default_client, store::STORE};
 
 pub async fn start_event_listener(
     lcu: Arc<RwLock<LcuClient>>,
@@ -27,6 +27,15 @@ pub async fn start_event_listener(
         .into_websocket()
         .await?;
 
+    // 段位缓存和相遇索引只在本次连接中有效，切换账号后需要重新建立
+    CONTEXT.ranked_cache.write().unwrap().clear();
+    *CONTEXT.encounter_index.write().unwrap() = Default::default();
+    let retention_days = CONTEXT.retention_days.load(Ordering::Relaxed);
+    match STORE.purge(retention_days, chrono::Utc::now().timestamp_millis()) {
+        Ok(0) => {}
+        Ok(removed) => info!("已清理{removed}场超过{retention_days}天的本地对局"),
+        Err(e) => error!("清理本地对局失败: {e}"),
+    }
     for event in SUBSCRIBED_EVENT {
         ws.send(Message::Text(format!("[5, \"{event}\"]"))).await?;
     }
@@ -43,7 +52,7 @@ pub async fn start_event_listener(
                 });
         });
     }
-    if CONTEXT.auto_pick.read().unwrap().unselected.is_empty() {
+    if CONTEXT.champions.read().unwrap().is_empty() {
         let lcu = lcu.clone();
         tokio::spawn(async move {
             let champions = lcu
@@ -55,7 +64,7 @@ pub async fn start_event_listener(
                     error!("加载自动选择数据失败: {e}");
                     vec![]
                 });
-            CONTEXT.auto_pick.write().unwrap().unselected = champions;
+            *CONTEXT.champions.write().unwrap() = champions;
         });
     }
     info!("客户端监听已启动");
@@ -1,8 +1,23 @@
-use std::sync::atomic::Ordering;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU16, Ordering};
+use std::time::{Duration, Instant};
 
-use log::info;
+use log::{debug, error, info, warn};
+use tokio::time::sleep;
 
-use crate::{CONTEXT, LcuClient, Result, events::EventType};
+use crate::{
+    CONTEXT, LcuClient, LockMode, Position, Result,
+    api::{item_sets::ItemSet, perks::RunePage},
+    context::{
+        AutoPick, Champion, Loadout, LoadoutTarget, SkinStrategy, SwapRule, SwapRules, spell_name,
+    },
+    events::{
+        EventType,
+        champ_select_state::{ActionKind, ChampSelectPhase, Transition},
+    },
+    library::{self, ITEM_SETS, RUNES},
+    notes::alert_players,
+};
 use serde::{Deserialize, Deserializer};
 
 #[derive(Debug, Deserialize)]
@@ -11,12 +26,80 @@ pub struct ChampSelectData {
     #[serde(deserialize_with = "deserialize_champion_ids")]
     pub bench_champions: Vec<u16>, // Vec<ChampionId>
     pub bench_enabled: bool,
+    #[serde(default)]
+    pub rerolls_remaining: u8,
     pub allow_subset_champion_picks: bool,
     #[serde(deserialize_with = "unwrap_actions")]
     pub actions: Vec<Action>,
     pub local_player_cell_id: u8, // cell_id
-    // pub id: String,
+    // 英雄选择会话ID，有人秒退后重新进入英雄选择时会变化
+    #[serde(default)]
+    pub id: String,
+    #[serde(default)]
+    pub game_id: u64,
     pub my_team: Vec<ChampSelectPlayer>, // 队友列表
+    #[serde(default)]
+    pub their_team: Vec<ChampSelectPlayer>, // 敌方列表，排位中信息被隐藏
+    #[serde(default)]
+    pub bans: Bans,
+    #[serde(default)]
+    pub timer: Timer,
+    #[serde(default)]
+    pub trades: Vec<SwapRequest>,
+    #[serde(default)]
+    pub pick_order_swaps: Vec<SwapRequest>,
+    #[serde(default)]
+    pub position_swaps: Vec<SwapRequest>,
+}
+
+/// 交换英雄、选择顺序或位置的请求
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SwapRequest {
+    pub id: u64,
+    // 对方的cell_id
+    pub cell_id: u8,
+    // RECEIVED 表示收到对方的请求
+    pub state: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SwapKind {
+    Trade,
+    PickOrder,
+    Position,
+}
+
+impl SwapKind {
+    pub fn name(self) -> &'static str {
+        match self {
+            SwapKind::Trade => "交换英雄",
+            SwapKind::PickOrder => "交换选择顺序",
+            SwapKind::Position => "交换位置",
+        }
+    }
+
+    pub(crate) fn api(self) -> &'static str {
+        match self {
+            SwapKind::Trade => "trades",
+            SwapKind::PickOrder => "pick-order-swaps",
+            SwapKind::Position => "position-swaps",
+        }
+    }
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Bans {
+    pub my_team_bans: Vec<u16>,
+    pub their_team_bans: Vec<u16>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Timer {
+    pub adjusted_time_left_in_phase: i64, // 毫秒
+    pub phase: String,
 }
 
 #[derive(Debug, Deserialize)]
@@ -40,6 +123,264 @@ pub struct ChampSelectPlayer {
     pub puuid: String,
     pub summoner_id: u64,
     pub champion_id: u16,
+    #[serde(default)]
+    pub champion_pick_intent: u16,
+    #[serde(default, deserialize_with = "deserialize_position")]
+    pub assigned_position: Option<Position>,
+    #[serde(default)]
+    pub selected_skin_id: u32,
+}
+
+/// 英雄在本次英雄选择中不可用的原因
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SkipReason {
+    Banned,
+    Picked,
+    TeammateIntent,
+}
+
+impl SkipReason {
+    pub fn name(self) -> &'static str {
+        match self {
+            SkipReason::Banned => "已被禁用",
+            SkipReason::Picked => "已被选择",
+            SkipReason::TeammateIntent => "队友预选",
+        }
+    }
+}
+
+impl ChampSelectData {
+    /// 当前玩家
+    pub fn local_player(&self) -> Option<&ChampSelectPlayer> {
+        self.my_team
+            .iter()
+            .find(|player| player.cell_id == self.local_player_cell_id)
+    }
+
+    /// 收到的全部交换请求
+    pub fn received_swaps(&self) -> impl Iterator<Item = (SwapKind, &SwapRequest)> {
+        [
+            (SwapKind::Trade, &self.trades),
+            (SwapKind::PickOrder, &self.pick_order_swaps),
+            (SwapKind::Position, &self.position_swaps),
+        ]
+        .into_iter()
+        .flat_map(|(kind, requests)| requests.iter().map(move |request| (kind, request)))
+        .filter(|(_, request)| request.state == "RECEIVED")
+    }
+
+    /// 根据规则判断是否接受交换请求，None表示不处理，同时返回判断原因
+    pub fn swap_decision(
+        &self,
+        kind: SwapKind,
+        request: &SwapRequest,
+        rules: &SwapRules,
+        auto_pick: &AutoPick,
+    ) -> Option<(bool, String)> {
+        let rule = match kind {
+            SwapKind::Trade => rules.trade,
+            SwapKind::PickOrder => rules.pick_order,
+            SwapKind::Position => rules.position,
+        };
+        match rule {
+            SwapRule::Ignore => return None,
+            SwapRule::Accept => return Some((true, rule.name().to_string())),
+            SwapRule::Decline => return Some((false, rule.name().to_string())),
+            SwapRule::Smart => {}
+        }
+        let me = self.local_player()?;
+        let other = self
+            .my_team
+            .iter()
+            .find(|player| player.cell_id == request.cell_id)?;
+        let candidates = auto_pick.candidates(me.assigned_position);
+        let rank = |champion_id: u16| {
+            candidates
+                .iter()
+                .position(|champion| champion.0 == champion_id)
+                .unwrap_or(usize::MAX)
+        };
+        let decision = match kind {
+            SwapKind::Trade => {
+                let better = rank(other.champion_id) < rank(me.champion_id);
+                let reason = if better {
+                    "对方英雄优先级更高"
+                } else {
+                    "对方英雄不在列表中或优先级更低"
+                };
+                (better, reason.to_string())
+            }
+            SwapKind::PickOrder => {
+                // 选择操作的顺序即选择顺序
+                let order = |cell_id: u8| {
+                    self.actions
+                        .iter()
+                        .position(|action| {
+                            action.actor_cell_id == cell_id && action.action_type == "pick"
+                        })
+                        .unwrap_or(usize::MAX)
+                };
+                let (mine, theirs) = (order(me.cell_id), order(request.cell_id));
+                if theirs >= mine {
+                    return Some((false, "选择顺序会推后".to_string()));
+                }
+                // 能拿到的最优先的英雄，换到更早的位置后，原本在自己之前选择的队友的预选不再冲突，
+                // 对方的预选仍然保留
+                let best = |earlier: bool| {
+                    let mut blocked = self.banned_champions();
+                    blocked.extend(self.picked_champions());
+                    for player in &self.my_team {
+                        let passed = earlier
+                            && player.cell_id != request.cell_id
+                            && (theirs..mine).contains(&order(player.cell_id));
+                        if player.cell_id != me.cell_id && !passed {
+                            blocked.extend([player.champion_id, player.champion_pick_intent]);
+                        }
+                    }
+                    candidates
+                        .iter()
+                        .position(|champion| !blocked.contains(&champion.0))
+                };
+                match (best(true), best(false)) {
+                    (Some(earlier), now) if now.is_none_or(|now| earlier < now) => {
+                        (true, format!("更早选择可以拿到{}", candidates[earlier].1))
+                    }
+                    _ => (false, "更早选择也拿不到优先级更高的英雄".to_string()),
+                }
+            }
+            SwapKind::Position => {
+                // 不在位置偏好中的位置排在最后
+                let rank = |position: Option<Position>| {
+                    position
+                        .and_then(|position| {
+                            auto_pick
+                                .position_preference
+                                .iter()
+                                .position(|&preferred| preferred == position)
+                        })
+                        .unwrap_or(usize::MAX)
+                };
+                let target = other.assigned_position;
+                let name = target.map_or("未知位置", Position::name);
+                if rank(target) < rank(me.assigned_position) {
+                    (true, format!("{name}在位置偏好中更靠前"))
+                } else {
+                    (false, format!("{name}不比当前位置更优先"))
+                }
+            }
+        };
+        Some(decision)
+    }
+
+    /// 当前玩家是否已锁定英雄，大乱斗中没有选择操作，视为已锁定
+    pub fn local_champion_locked(&self) -> bool {
+        self.bench_enabled
+            || self.actions.iter().any(|action| {
+                action.actor_cell_id == self.local_player_cell_id
+                    && action.action_type == "pick"
+                    && action.completed
+            })
+    }
+
+    /// 已锁定且尚未设置过的英雄，applied 记录已设置的英雄，同一英雄只返回一次
+    pub(crate) fn newly_locked_champion(&self, applied: &AtomicU16) -> Option<u16> {
+        let champion_id = self.local_player()?.champion_id;
+        if champion_id == 0 || !self.local_champion_locked() {
+            return None;
+        }
+        (applied.swap(champion_id, Ordering::Relaxed) != champion_id).then_some(champion_id)
+    }
+
+    /// 当前玩家尚未完成的指定类型操作，计划阶段时操作尚未开始
+    pub fn local_action(&self, action_type: &str) -> Option<&Action> {
+        self.actions.iter().find(|action| {
+            action.actor_cell_id == self.local_player_cell_id
+                && action.action_type == action_type
+                && !action.completed
+        })
+    }
+
+    /// 当前玩家正在进行的指定类型操作（pick/ban）
+    pub fn local_action_in_progress(&self, action_type: &str) -> Option<&Action> {
+        self.actions.iter().find(|action| {
+            action.actor_cell_id == self.local_player_cell_id
+                && action.action_type == action_type
+                && action.is_in_progress
+        })
+    }
+
+    /// 已被禁用的英雄，包括双方的禁用列表和已完成的禁用操作
+    pub fn banned_champions(&self) -> Vec<u16> {
+        self.actions
+            .iter()
+            .filter(|action| action.action_type == "ban" && action.completed)
+            .map(|action| action.champion_id)
+            .chain(self.bans.my_team_bans.iter().copied())
+            .chain(self.bans.their_team_bans.iter().copied())
+            .filter(|&id| id != 0)
+            .collect()
+    }
+
+    /// 其他玩家已锁定的英雄
+    pub fn picked_champions(&self) -> Vec<u16> {
+        self.actions
+            .iter()
+            .filter(|action| {
+                action.action_type == "pick"
+                    && action.completed
+                    && action.actor_cell_id != self.local_player_cell_id
+            })
+            .map(|action| action.champion_id)
+            .filter(|&id| id != 0)
+            .collect()
+    }
+
+    /// 队友正在预选或已声明意向的英雄
+    pub fn teammate_intents(&self) -> Vec<u16> {
+        self.my_team
+            .iter()
+            .filter(|player| player.cell_id != self.local_player_cell_id)
+            .flat_map(|player| [player.champion_id, player.champion_pick_intent])
+            .filter(|&id| id != 0)
+            .collect()
+    }
+
+    /// 所有不可用的英雄及原因，同一英雄按禁用、已选、队友预选的优先级记录
+    pub fn unavailable_champions(&self) -> HashMap<u16, SkipReason> {
+        let mut unavailable = HashMap::new();
+        let reasons = [
+            (self.teammate_intents(), SkipReason::TeammateIntent),
+            (self.picked_champions(), SkipReason::Picked),
+            (self.banned_champions(), SkipReason::Banned),
+        ];
+        for (champions, reason) in reasons {
+            for champion_id in champions {
+                unavailable.insert(champion_id, reason);
+            }
+        }
+        unavailable
+    }
+}
+
+/// 自动禁用或选择失败时，允许在下一次会话更新时重试，失败次数过多时放弃
+fn retry_turn(action_id: u8) {
+    if !CONTEXT.champ_select.lock().unwrap().retry_turn(action_id) {
+        warn!("自动操作多次失败，不再重试");
+    }
+}
+
+/// 过滤掉不可用的英雄并记录跳过原因
+fn available_champions<'a>(
+    champions: &'a [Champion],
+    unavailable: &'a HashMap<u16, SkipReason>,
+) -> impl Iterator<Item = &'a Champion> {
+    champions.iter().filter(|champion| {
+        let reason = unavailable.get(&champion.0);
+        if let Some(reason) = reason {
+            debug!("跳过英雄 {}: {}", champion.1, reason.name());
+        }
+        reason.is_none()
+    })
 }
 
 /// Deserialize champion IDs from a JSON array of objects
@@ -59,6 +400,15 @@ where
     Ok(wrappers.into_iter().map(|w| w.champion_id).collect())
 }
 
+/// 未分配位置时为空字符串
+fn deserialize_position<'de, D>(deserializer: D) -> std::result::Result<Option<Position>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let position = Option::<String>::deserialize(deserializer)?;
+    Ok(position.and_then(|position| position.parse().ok()))
+}
+
 fn unwrap_actions<'de, D>(deserializer: D) -> std::result::Result<Vec<Action>, D::Error>
 where
     D: Deserializer<'de>,
@@ -72,68 +422,459 @@ where
 }
 
 impl LcuClient {
-    /// 处理英雄选择事件，保存队伍信息并尝试自动选人
+    /// 处理英雄选择事件，尝试自动禁用和自动选人
     pub(crate) async fn handle_champ_select_event(&self, data: ChampSelectData) {
-        if !CONTEXT.auto_pick.read().unwrap().enabled || CONTEXT.picked.load(Ordering::Relaxed) {
+        let transitions = CONTEXT.champ_select.lock().unwrap().update(&data);
+        // 有人秒退后不经过房间直接进入新的英雄选择，需要清除上一次的状态
+        if transitions.contains(&Transition::SessionChanged) {
+            info!("进入新的英雄选择，清除上一次的状态");
+            CONTEXT.reset_champ_select();
+        }
+        *CONTEXT.unavailable_champions.write().unwrap() = data.unavailable_champions();
+        self.answer_swap_requests(&data).await;
+        let mut joined = vec![];
+        for transition in transitions {
+            debug!("英雄选择状态变化: {transition:?}");
+            match transition {
+                Transition::PhaseChanged(ChampSelectPhase::Planning) => {
+                    self.declare_pick_intent(&data).await;
+                }
+                Transition::MyTurn {
+                    kind: ActionKind::Ban,
+                    action_id,
+                } => self.auto_ban_champion(&data, action_id).await,
+                Transition::MyTurn {
+                    kind: ActionKind::Pick,
+                    action_id,
+                } => self.auto_pick_champion(&data, action_id).await,
+                Transition::TeammateLocked {
+                    cell_id,
+                    champion_id,
+                } => {
+                    let champions = CONTEXT.champions.read().unwrap();
+                    let name = champions
+                        .iter()
+                        .find(|champion| champion.0 == champion_id)
+                        .map_or(champion_id.to_string(), |champion| champion.1.clone());
+                    info!("队友({cell_id})锁定英雄: {name}");
+                }
+                // 轮到自己但尚未完成操作时提醒
+                Transition::TimerMilestone { seconds_left } => {
+                    for kind in [ActionKind::Ban, ActionKind::Pick] {
+                        if data.local_action_in_progress(kind.as_str()).is_some() {
+                            warn!("剩余 {seconds_left} 秒，尚未完成{}", kind.name());
+                        }
+                    }
+                }
+                // 匿名模式下 puuid 为空，无法查询战绩
+                Transition::TeammateJoined { puuid, .. } if !puuid.is_empty() => joined.push(puuid),
+                _ => {}
+            }
+        }
+        alert_players(joined.iter().map(String::as_str), "英雄选择");
+        self.spawn_team_analysis(joined);
+        if data.bench_enabled {
+            self.snipe_bench_champion(&data).await;
+        }
+        self.apply_loadout(&data).await;
+    }
+
+    /// 按当前方案的规则处理收到的交换请求，每个请求只处理一次
+    async fn answer_swap_requests(&self, data: &ChampSelectData) {
+        let (rules, auto_pick) = {
+            let profiles = CONTEXT.profiles.read().unwrap();
+            let profile = profiles.active();
+            (profile.swaps, profile.auto_pick.clone())
+        };
+        for (kind, request) in data.received_swaps() {
+            if !CONTEXT.handled_swaps.lock().unwrap().insert(request.id) {
+                continue;
+            }
+            let Some((accept, reason)) = data.swap_decision(kind, request, &rules, &auto_pick)
+            else {
+                info!("收到{}请求，未处理", kind.name());
+                continue;
+            };
+            let answer = if accept { "接受" } else { "拒绝" };
+            info!("{answer}{}请求: {reason}", kind.name());
+            if let Err(e) = self.answer_swap(kind, request.id, accept).await {
+                error!("{answer}{}请求失败: {e}", kind.name());
+            }
+        }
+    }
+
+    /// 锁定英雄后按预设设置召唤师技能、皮肤、符文和出装，使用常用皮肤时记录手动选择的皮肤
+    async fn apply_loadout(&self, data: &ChampSelectData) {
+        let loadout = { CONTEXT.loadout.read().unwrap().clone() };
+        let Some(player) = data.local_player() else {
+            return;
+        };
+        if !loadout.enabled {
             return;
         }
-        // 当前玩家不在英雄选择阶段
-        if !data.actions.iter().any(|action| {
-            action.actor_cell_id == data.local_player_cell_id
-                && action.action_type == "pick"
-                && action.is_in_progress
-        }) {
+        let Some(champion_id) = data.newly_locked_champion(&CONTEXT.loadout_champion) else {
+            // 已设置过的英雄只记录手动选择的皮肤
+            let champion_id = player.champion_id;
+            if champion_id != 0
+                && champion_id == CONTEXT.loadout_champion.load(Ordering::Relaxed)
+                && loadout.skin_strategy == SkinStrategy::Favorite
+                && player.selected_skin_id != 0
+                && loadout.favorite_skins.get(&champion_id) != Some(&player.selected_skin_id)
+            {
+                CONTEXT
+                    .loadout
+                    .write()
+                    .unwrap()
+                    .favorite_skins
+                    .insert(champion_id, player.selected_skin_id);
+            }
             return;
+        };
+
+        let mut selection = serde_json::Map::new();
+        let game_mode = CONTEXT.game_mode.read().unwrap().clone();
+        if let Some(preset) =
+            loadout.spell_preset(champion_id, &game_mode, player.assigned_position)
+        {
+            info!(
+                "自动设置召唤师技能: {} {}",
+                spell_name(preset.spell1),
+                spell_name(preset.spell2)
+            );
+            selection.insert("spell1Id".to_string(), preset.spell1.into());
+            selection.insert("spell2Id".to_string(), preset.spell2.into());
+        }
+        match self.choose_skin(champion_id, &loadout).await {
+            Ok(Some(skin_id)) => {
+                info!("自动选择皮肤: {skin_id}");
+                selection.insert("selectedSkinId".to_string(), skin_id.into());
+            }
+            Ok(None) => {}
+            Err(e) => error!("获取皮肤列表失败: {e}"),
+        }
+        if !selection.is_empty()
+            && let Err(e) = self.update_my_selection(&selection.into()).await
+        {
+            error!("设置召唤师技能和皮肤失败: {e}");
         }
+        if loadout.runes {
+            self.apply_rune_page(champion_id, &game_mode, player.assigned_position)
+                .await;
+        }
+        if loadout.item_sets {
+            self.apply_item_sets(champion_id, &game_mode, player.assigned_position)
+                .await;
+        }
+    }
 
-        let selected = { CONTEXT.auto_pick.read().unwrap().selected.clone() };
+    /// 从本地符文库中选择最匹配的符文页写入客户端
+    async fn apply_rune_page(&self, champion_id: u16, game_mode: &str, position: Option<Position>) {
+        let pages = library::load::<RunePage>(RUNES);
+        let Some(page) = LoadoutTarget::best_match(
+            &pages,
+            |page| &page.target,
+            champion_id,
+            game_mode,
+            position,
+        ) else {
+            debug!("符文库中没有匹配的符文页");
+            return;
+        };
+        match self.set_managed_rune_page(page).await {
+            Ok(()) => info!("自动设置符文页: {}", page.name),
+            Err(e) => error!("设置符文页失败: {e}"),
+        }
+    }
 
-        // 大乱斗英雄选择
-        if !CONTEXT.subset_champion_checked.load(Ordering::Relaxed)
-            && data.allow_subset_champion_picks
-            && let Ok(subset_champions) = self.subset_champion_list().await
+    /// 按皮肤选择方式挑选皮肤，None表示不修改
+    async fn choose_skin(&self, champion_id: u16, loadout: &Loadout) -> Result<Option<u32>> {
+        let with_chromas = match loadout.skin_strategy {
+            SkinStrategy::Default => return Ok(Some(champion_id as u32 * 1000)),
+            SkinStrategy::Favorite => return Ok(loadout.favorite_skins.get(&champion_id).copied()),
+            SkinStrategy::Random => false,
+            SkinStrategy::RandomChroma => true,
+        };
+        let summoner_id = CONTEXT.me.read().unwrap().summoner_id;
+        let skins = self.get_champion_skins(summoner_id, champion_id).await?;
+        let mut owned = skins
+            .iter()
+            .filter(|skin| skin.ownership.owned && !skin.is_base)
+            .map(|skin| skin.id)
+            .collect::<Vec<_>>();
+        if with_chromas {
+            owned.extend(
+                skins
+                    .iter()
+                    .flat_map(|skin| &skin.chromas)
+                    .filter(|chroma| chroma.ownership.owned)
+                    .map(|chroma| chroma.id),
+            );
+        }
+        Ok(fastrand::choice(owned))
+    }
+
+    /// 大乱斗中持续检查备选席，出现优先级更高的英雄时交换，当前英雄排名过低时使用重随
+    async fn snipe_bench_champion(&self, data: &ChampSelectData) {
+        let auto_pick = { CONTEXT.profiles.read().unwrap().active().auto_pick.clone() };
+        let Some(current) = data.local_player().map(|player| player.champion_id) else {
+            return;
+        };
+        if !auto_pick.enabled || auto_pick.selected.is_empty() || current == 0 {
+            return;
+        }
+        // 避免频繁交换
+        let cooldown = Duration::from_secs(auto_pick.bench.cooldown_seconds as u64);
+        if CONTEXT
+            .last_bench_action
+            .lock()
+            .unwrap()
+            .is_some_and(|last| last.elapsed() < cooldown)
         {
-            for champion in selected.iter() {
-                if subset_champions.contains(&champion.0)
-                    && self
-                        .pick_champion(champion.0, data.local_player_cell_id)
-                        .await
-                        .is_ok()
-                {
-                    info!("自动选择英雄: {}", champion.1);
-                    CONTEXT.champion_id.store(champion.0, Ordering::Relaxed);
-                    CONTEXT.picked.store(true, Ordering::Relaxed);
-                    return;
-                }
+            return;
+        }
+
+        // 不在列表中的英雄排在最后
+        let rank = |champion_id: u16| {
+            auto_pick
+                .selected
+                .iter()
+                .position(|champion| champion.0 == champion_id)
+        };
+        let current_rank = rank(current).unwrap_or(usize::MAX);
+        if let Some(best) = data.bench_champions.iter().filter_map(|&id| rank(id)).min()
+            && best < current_rank
+        {
+            let champion = &auto_pick.selected[best];
+            if self.swap_champion(champion.0).await.is_ok() {
+                info!("自动交换英雄: {}", champion.1);
+                CONTEXT.champion_id.store(champion.0, Ordering::Relaxed);
+                *CONTEXT.last_bench_action.lock().unwrap() = Some(Instant::now());
             }
+            return;
+        }
 
-            CONTEXT
-                .subset_champion_checked
-                .store(true, Ordering::Release);
+        let reroll_below = auto_pick.bench.reroll_below as usize;
+        if reroll_below > 0
+            && current_rank >= reroll_below
+            && data.rerolls_remaining > 0
+            && self.reroll_champion().await.is_ok()
+        {
+            info!("当前英雄不在优先列表前 {reroll_below} 位，已使用重随");
+            *CONTEXT.last_bench_action.lock().unwrap() = Some(Instant::now());
         }
+    }
 
-        if data.bench_enabled {
-            for champion in selected.iter() {
-                if data.bench_champions.contains(&champion.0)
-                    && self.swap_champion(champion.0).await.is_ok()
-                {
-                    info!("自动选择英雄: {}", champion.1);
-                    CONTEXT.champion_id.store(champion.0, Ordering::Relaxed);
-                    CONTEXT.picked.store(true, Ordering::Relaxed);
-                    return;
-                }
+    /// 按优先级禁用英雄，跳过队友预选的英雄和已被禁用的英雄
+    async fn auto_ban_champion(&self, data: &ChampSelectData, action_id: u8) {
+        let auto_ban = { CONTEXT.profiles.read().unwrap().active().auto_ban.clone() };
+        if !auto_ban.enabled || auto_ban.selected.is_empty() {
+            return;
+        }
+
+        let wait_ms = data.timer.adjusted_time_left_in_phase - auto_ban.wait_seconds as i64 * 1000;
+        if auto_ban.wait_seconds > 0 && wait_ms > 0 {
+            info!(
+                "将在禁用阶段剩余 {} 秒时自动禁用英雄",
+                auto_ban.wait_seconds
+            );
+            // 在后台等待，避免阻塞其他事件的处理
+            let client = self.clone();
+            tokio::spawn(async move {
+                client
+                    .delayed_ban(auto_ban.selected, action_id, wait_ms as u64)
+                    .await
+            });
+            return;
+        }
+        if !self
+            .ban_first_available(&auto_ban.selected, data, action_id)
+            .await
+        {
+            retry_turn(action_id);
+        }
+    }
+
+    /// 等待后重新获取会话信息，禁用操作仍在进行时再禁用
+    async fn delayed_ban(&self, selected: Vec<Champion>, action_id: u8, wait_ms: u64) {
+        sleep(Duration::from_millis(wait_ms)).await;
+        // 等待期间队友可能更换了预选英雄，重新获取会话信息
+        let session = match self.get_champ_select_session().await {
+            Ok(session) => session,
+            Err(e) => {
+                error!("获取英雄选择信息失败: {e}");
+                retry_turn(action_id);
+                return;
+            }
+        };
+        // 等待期间已手动禁用或已离开英雄选择
+        if session
+            .local_action_in_progress("ban")
+            .is_none_or(|action| action.id != action_id)
+        {
+            return;
+        }
+        if !self
+            .ban_first_available(&selected, &session, action_id)
+            .await
+        {
+            retry_turn(action_id);
+        }
+    }
+
+    /// 按优先级禁用第一个可用的英雄，返回是否禁用成功
+    async fn ban_first_available(
+        &self,
+        selected: &[Champion],
+        data: &ChampSelectData,
+        action_id: u8,
+    ) -> bool {
+        let unavailable = data.unavailable_champions();
+        for champion in available_champions(selected, &unavailable) {
+            if self.ban_champion(champion.0, action_id).await.is_ok() {
+                info!("自动禁用英雄: {}", champion.1);
+                return true;
             }
+        }
+        info!("禁用列表中没有可禁用的英雄");
+        false
+    }
+
+    /// 轮到自己选择时按优先级自动选择英雄
+    async fn auto_pick_champion(&self, data: &ChampSelectData, action_id: u8) {
+        let auto_pick = { CONTEXT.profiles.read().unwrap().active().auto_pick.clone() };
+        if !auto_pick.enabled {
+            return;
+        }
+        let position = data
+            .local_player()
+            .and_then(|player| player.assigned_position);
+        let candidates = auto_pick.candidates(position);
+        let unavailable = data.unavailable_champions();
+
+        // 只能从部分英雄中选择的模式
+        let subset_champions = if data.allow_subset_champion_picks {
+            self.subset_champion_list().await.ok()
         } else {
-            for champion in selected.into_iter() {
-                if self
-                    .pick_champion(champion.0, data.local_player_cell_id)
+            None
+        };
+        for champion in available_champions(&candidates, &unavailable) {
+            if subset_champions
+                .as_ref()
+                .is_some_and(|subset| !subset.contains(&champion.0))
+            {
+                continue;
+            }
+            if self
+                .pick_and_lock(champion, action_id, &auto_pick, data)
+                .await
+            {
+                info!("自动选择英雄: {}", champion.1);
+                return;
+            }
+        }
+        retry_turn(action_id);
+    }
+
+    /// 计划阶段预选第一个可用的英雄作为意向，让队友提前看到
+    async fn declare_pick_intent(&self, data: &ChampSelectData) {
+        let auto_pick = { CONTEXT.profiles.read().unwrap().active().auto_pick.clone() };
+        if !auto_pick.enabled || !auto_pick.declare_intent {
+            return;
+        }
+        let Some(action_id) = data.local_action("pick").map(|action| action.id) else {
+            return;
+        };
+        let position = data
+            .local_player()
+            .and_then(|player| player.assigned_position);
+        let candidates = auto_pick.candidates(position);
+        let unavailable = data.unavailable_champions();
+        for champion in available_champions(&candidates, &unavailable) {
+            if self
+                .pick_champion(champion.0, action_id, false)
+                .await
+                .is_ok()
+            {
+                info!("声明英雄意向: {}", champion.1);
+                return;
+            }
+        }
+    }
+
+    /// 按锁定方式选择英雄，需要等待时先预选，返回是否选择成功
+    async fn pick_and_lock(
+        &self,
+        champion: &Champion,
+        action_id: u8,
+        auto_pick: &AutoPick,
+        data: &ChampSelectData,
+    ) -> bool {
+        let lock_ms = auto_pick.lock_seconds as i64 * 1000;
+        let wait_ms = match auto_pick.lock_mode {
+            LockMode::Immediately => {
+                return self
+                    .pick_champion(champion.0, action_id, true)
                     .await
-                    .is_ok()
-                {
-                    info!("自动选择英雄: {}", champion.1);
-                    CONTEXT.picked.store(true, Ordering::Relaxed);
-                    return;
-                }
+                    .is_ok();
+            }
+            LockMode::HoverOnly => 0,
+            LockMode::AfterDelay => lock_ms,
+            LockMode::LastSeconds => (data.timer.adjusted_time_left_in_phase - lock_ms).max(0),
+        };
+        if self
+            .pick_champion(champion.0, action_id, false)
+            .await
+            .is_err()
+        {
+            return false;
+        }
+        if auto_pick.lock_mode == LockMode::HoverOnly {
+            return true;
+        }
+
+        info!(
+            "已预选英雄: {}，将在 {} 秒后锁定",
+            champion.1,
+            wait_ms / 1000
+        );
+        // 在后台等待，避免阻塞交换请求等其他事件的处理
+        let client = self.clone();
+        let champion = champion.clone();
+        tokio::spawn(async move {
+            client
+                .delayed_lock(champion, action_id, wait_ms as u64)
+                .await
+        });
+        true
+    }
+
+    /// 等待后重新获取会话信息，选择操作仍在进行且预选的英雄未变时再锁定
+    async fn delayed_lock(&self, champion: Champion, action_id: u8, wait_ms: u64) {
+        sleep(Duration::from_millis(wait_ms)).await;
+        let session = match self.get_champ_select_session().await {
+            Ok(session) => session,
+            Err(e) => {
+                error!("获取英雄选择信息失败: {e}");
+                retry_turn(action_id);
+                return;
+            }
+        };
+        let Some(action) = session
+            .local_action_in_progress("pick")
+            .filter(|action| action.id == action_id)
+        else {
+            // 等待期间已手动锁定或已离开英雄选择
+            return;
+        };
+        if action.champion_id != champion.0 {
+            info!("等待期间已手动更换英雄，不再自动锁定");
+            return;
+        }
+        match self.pick_champion(champion.0, action_id, true).await {
+            Ok(()) => info!("已锁定英雄: {}", champion.1),
+            Err(e) => {
+                error!("锁定英雄失败: {e}");
+                retry_turn(action_id);
             }
         }
     }
@@ -143,9 +884,236 @@ impl LcuClient {
         event_type: EventType,
         data: u16,
     ) -> Result<()> {
-        if event_type == EventType::Create {
+        if matches!(event_type, EventType::Create | EventType::Update) {
             CONTEXT.champion_id.store(data, Ordering::Relaxed);
         }
         Ok(())
     }
+
+    /// 将出装库中适用于当前英雄和模式的全部出装方案写入客户端
+    async fn apply_item_sets(&self, champion_id: u16, game_mode: &str, position: Option<Position>) {
+        let item_sets = library::load::<ItemSet>(ITEM_SETS);
+        let matched = ItemSet::matching(&item_sets, champion_id, game_mode, position);
+        if matched.is_empty() {
+            debug!("出装库中没有匹配的出装方案");
+            return;
+        }
+        match self.set_managed_item_sets(champion_id, &matched).await {
+            Ok(()) => info!("已写入 {} 个出装方案", matched.len()),
+            Err(e) => error!("写入出装方案失败: {e}"),
+        }
+    }
+}
+
+#[test]
+fn test_unavailable_champions() {
+    use crate::fixtures::{self, action};
+    use serde_json::json;
+
+    // 队友预选的英雄来自快照：0 号 86、1 号 64、3 号 222、4 号 412
+    let data: ChampSelectData = fixtures::load(fixtures::CHAMP_SELECT, |session| {
+        session["actions"] = json!([
+            [
+                action(1, 0, "ban", 555, true, false),
+                action(6, 5, "ban", 0, true, false)
+            ],
+            [action(12, 5, "pick", 121, true, false)],
+            [action(15, 2, "pick", 103, false, true)]
+        ]);
+        session["bans"]["myTeamBans"] = json!([555]);
+        session["bans"]["theirTeamBans"] = json!([157]);
+        session["myTeam"][2]["championId"] = 103.into();
+    });
+    assert_eq!(
+        data.local_player().unwrap().assigned_position,
+        Some(Position::Middle)
+    );
+    assert_eq!(data.local_action_in_progress("pick").unwrap().id, 15);
+
+    let unavailable = data.unavailable_champions();
+    assert_eq!(unavailable.get(&555), Some(&SkipReason::Banned));
+    assert_eq!(unavailable.get(&157), Some(&SkipReason::Banned));
+    assert_eq!(unavailable.get(&121), Some(&SkipReason::Picked));
+    assert_eq!(unavailable.get(&412), Some(&SkipReason::TeammateIntent));
+    // 自己预选的英雄不算不可用
+    assert_eq!(unavailable.get(&103), None);
+
+    // 位置列表中的英雄都不可用时使用通用列表
+    let auto_pick = AutoPick {
+        selected: vec![
+            Champion(412, "锤石".to_string()),
+            Champion(555, "派克".to_string()),
+            Champion(103, "阿狸".to_string()),
+        ],
+        positions: [(
+            Position::Middle,
+            vec![
+                Champion(555, "派克".to_string()),
+                Champion(64, "李青".to_string()),
+            ],
+        )]
+        .into(),
+        ..Default::default()
+    };
+    let candidates = auto_pick.candidates(Some(Position::Middle));
+    assert_eq!(
+        candidates
+            .iter()
+            .map(|champion| champion.0)
+            .collect::<Vec<_>>(),
+        vec![555, 64, 412, 103]
+    );
+    let available = available_champions(&candidates, &unavailable)
+        .map(|champion| champion.0)
+        .collect::<Vec<_>>();
+    assert_eq!(available, vec![103]);
+}
+
+#[test]
+fn test_swap_decision() {
+    use crate::fixtures::{self, action};
+    use serde_json::json;
+
+    // 选择顺序依次为 4、1、2（自己）、3，1 号打野预选李青，4 号辅助选择了金克丝
+    let mut data: ChampSelectData = fixtures::load(fixtures::CHAMP_SELECT, |session| {
+        session["actions"] = json!([[
+            action(11, 4, "pick", 0, false, true),
+            action(12, 1, "pick", 0, false, false),
+            action(13, 2, "pick", 0, false, false),
+            action(14, 3, "pick", 0, false, false)
+        ]]);
+        session["myTeam"][2]["championId"] = 103.into();
+        session["myTeam"][4]["championId"] = 222.into();
+        session["trades"] = json!([{"id": 1, "cellId": 4, "state": "RECEIVED"}]);
+        session["pickOrderSwaps"] = json!([{"id": 2, "cellId": 4, "state": "RECEIVED"}]);
+        session["positionSwaps"] = json!([
+            {"id": 3, "cellId": 4, "state": "RECEIVED"},
+            {"id": 4, "cellId": 4, "state": "AVAILABLE"}
+        ]);
+    });
+    let requests = data.received_swaps().collect::<Vec<_>>();
+    assert_eq!(requests.len(), 3);
+
+    let auto_pick = AutoPick {
+        positions: [(
+            Position::Middle,
+            vec![
+                Champion(64, "李青".to_string()),
+                Champion(222, "金克丝".to_string()),
+                Champion(103, "阿狸".to_string()),
+            ],
+        )]
+        .into(),
+        position_preference: vec![Position::Middle, Position::Jungle],
+        ..Default::default()
+    };
+    let rules = SwapRules {
+        trade: SwapRule::Smart,
+        pick_order: SwapRule::Smart,
+        position: SwapRule::Smart,
+    };
+    let decisions = requests
+        .iter()
+        .map(|(kind, request)| {
+            data.swap_decision(*kind, request, &rules, &auto_pick)
+                .map(|(accept, _)| accept)
+        })
+        .collect::<Vec<_>>();
+    // 对方英雄优先级更高、更早选择可以在 1 号之前拿到李青、辅助不在位置偏好中
+    assert_eq!(decisions, vec![Some(true), Some(true), Some(false)]);
+
+    let ignore = SwapRules::default();
+    let (kind, request) = &requests[0];
+    assert_eq!(
+        data.swap_decision(*kind, request, &ignore, &auto_pick),
+        None
+    );
+
+    let decide = |data: &ChampSelectData, kind: SwapKind, cell_id: u8, auto_pick: &AutoPick| {
+        let request = SwapRequest {
+            id: 9,
+            cell_id,
+            state: "RECEIVED".to_string(),
+        };
+        data.swap_decision(kind, &request, &rules, auto_pick)
+            .map(|(accept, _)| accept)
+    };
+    // 换到更靠后的位置
+    assert_eq!(
+        decide(&data, SwapKind::PickOrder, 3, &auto_pick),
+        Some(false)
+    );
+    // 打野在位置偏好中排在中单之后，不离开常用位置
+    assert_eq!(
+        decide(&data, SwapKind::Position, 1, &auto_pick),
+        Some(false)
+    );
+    let jungle_first = AutoPick {
+        position_preference: vec![Position::Jungle, Position::Middle],
+        ..auto_pick.clone()
+    };
+    assert_eq!(
+        decide(&data, SwapKind::Position, 1, &jungle_first),
+        Some(true)
+    );
+    // 中间的队友不再预选李青后，更早选择也拿不到更优先的英雄
+    data.my_team[1].champion_pick_intent = 0;
+    data.my_team[2].champion_id = 64;
+    assert_eq!(
+        decide(&data, SwapKind::PickOrder, 4, &auto_pick),
+        Some(false)
+    );
+}
+
+#[test]
+fn test_loadout_item_sets() {
+    use crate::fixtures::{self, action};
+    use serde_json::json;
+
+    let snapshot = |completed: bool| -> ChampSelectData {
+        fixtures::load(fixtures::CHAMP_SELECT, |session| {
+            session["actions"] = json!([[action(15, 2, "pick", 103, completed, !completed)]]);
+            session["myTeam"][2]["championId"] = 103.into();
+        })
+    };
+    let applied = AtomicU16::new(0);
+    // 预选时不设置
+    assert_eq!(snapshot(false).newly_locked_champion(&applied), None);
+    // 锁定后设置一次
+    assert_eq!(snapshot(true).newly_locked_champion(&applied), Some(103));
+    assert_eq!(snapshot(true).newly_locked_champion(&applied), None);
+
+    let item_set = |title: &str, target: LoadoutTarget| ItemSet {
+        title: title.to_string(),
+        target,
+        blocks: vec![],
+    };
+    let item_sets = [
+        item_set(
+            "阿狸",
+            LoadoutTarget {
+                champion_id: Some(103),
+                ..Default::default()
+            },
+        ),
+        item_set(
+            "大乱斗",
+            LoadoutTarget {
+                game_mode: Some("ARAM".to_string()),
+                ..Default::default()
+            },
+        ),
+        item_set(
+            "中单通用",
+            LoadoutTarget {
+                position: Some(Position::Middle),
+                ..Default::default()
+            },
+        ),
+    ];
+    let titles = ItemSet::matching(&item_sets, 103, "CLASSIC", Some(Position::Middle))
+        .iter()
+        .map(|set| set.title.as_str())
+        .collect::<Vec<_>>();
+    assert_eq!(titles, vec!["阿狸", "中单通用"]);
 }
@@ -0,0 +1,300 @@
+use std::collections::{HashMap, HashSet};
+
+use super::champ_select::ChampSelectData;
+
+// 剩余时间提醒的秒数
+const TIMER_MILESTONES: [i64; 2] = [10, 5];
+// 每个操作失败后最多重试的次数，避免无法完成的操作在每次会话更新时反复请求
+const MAX_TURN_RETRIES: u8 = 3;
+
+/// 英雄选择阶段，对应 timer.phase
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ChampSelectPhase {
+    // 计划阶段，可以声明英雄意向
+    Planning,
+    // 禁用和选择阶段
+    BanPick,
+    // 最终阶段，可以交换英雄和设置符文
+    Finalization,
+    GameStarting,
+    Other(String),
+}
+
+impl From<&str> for ChampSelectPhase {
+    fn from(phase: &str) -> Self {
+        match phase {
+            "PLANNING" => ChampSelectPhase::Planning,
+            "BAN_PICK" => ChampSelectPhase::BanPick,
+            "FINALIZATION" => ChampSelectPhase::Finalization,
+            "GAME_STARTING" => ChampSelectPhase::GameStarting,
+            other => ChampSelectPhase::Other(other.to_string()),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ActionKind {
+    Ban,
+    Pick,
+}
+
+impl ActionKind {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ActionKind::Ban => "ban",
+            ActionKind::Pick => "pick",
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            ActionKind::Ban => "禁用",
+            ActionKind::Pick => "选择",
+        }
+    }
+}
+
+/// 两次快照之间发生的变化
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Transition {
+    // 会话ID或游戏ID变化，之前记录的状态已清除，总是第一个触发
+    SessionChanged,
+    PhaseChanged(ChampSelectPhase),
+    // 轮到自己禁用或选择，每个操作只触发一次，操作失败后调用 retry_turn 可有限次地再次触发
+    MyTurn { kind: ActionKind, action_id: u8 },
+    TeammateJoined { cell_id: u8, puuid: String },
+    TeammateLocked { cell_id: u8, champion_id: u16 },
+    // 当前阶段剩余时间首次低于指定秒数
+    TimerMilestone { seconds_left: i64 },
+}
+
+/// 英雄选择状态机，根据会话快照计算状态变化，进入新的英雄选择时需要重置
+#[derive(Debug, Default)]
+pub struct ChampSelectState {
+    // (会话ID, 游戏ID)
+    session: Option<(String, u64)>,
+    phase: Option<ChampSelectPhase>,
+    time_left: i64,
+    turns: HashSet<u8>,
+    // 操作ID -> 已重试的次数
+    retries: HashMap<u8, u8>,
+    joined: HashSet<u8>,
+    locked: HashSet<u8>,
+}
+
+impl ChampSelectState {
+    pub fn phase(&self) -> Option<&ChampSelectPhase> {
+        self.phase.as_ref()
+    }
+
+    /// 自动禁用或选择失败后，在下一次快照中操作仍在进行时再次触发 MyTurn，
+    /// 已重试 MAX_TURN_RETRIES 次时不再触发并返回 false
+    pub fn retry_turn(&mut self, action_id: u8) -> bool {
+        let retries = self.retries.entry(action_id).or_default();
+        if *retries >= MAX_TURN_RETRIES {
+            return false;
+        }
+        *retries += 1;
+        self.turns.remove(&action_id);
+        true
+    }
+
+    pub fn update(&mut self, data: &ChampSelectData) -> Vec<Transition> {
+        let mut transitions = vec![];
+
+        let session = (data.id.clone(), data.game_id);
+        if self
+            .session
+            .as_ref()
+            .is_some_and(|current| *current != session)
+        {
+            *self = Self::default();
+            transitions.push(Transition::SessionChanged);
+        }
+        self.session = Some(session);
+
+        let phase = ChampSelectPhase::from(data.timer.phase.as_str());
+        let time_left = data.timer.adjusted_time_left_in_phase;
+        if self.phase.as_ref() != Some(&phase) {
+            self.phase = Some(phase.clone());
+            transitions.push(Transition::PhaseChanged(phase));
+        } else {
+            // 禁用选择阶段每轮操作都会重置计时
+            for seconds_left in TIMER_MILESTONES {
+                if self.time_left > seconds_left * 1000 && time_left <= seconds_left * 1000 {
+                    transitions.push(Transition::TimerMilestone { seconds_left });
+                }
+            }
+        }
+        self.time_left = time_left;
+
+        let me = data.local_player_cell_id;
+        for player in data.my_team.iter().filter(|player| player.cell_id != me) {
+            if self.joined.insert(player.cell_id) {
+                transitions.push(Transition::TeammateJoined {
+                    cell_id: player.cell_id,
+                    puuid: player.puuid.clone(),
+                });
+            }
+        }
+
+        for action in &data.actions {
+            let teammate = action.actor_cell_id != me
+                && data
+                    .my_team
+                    .iter()
+                    .any(|player| player.cell_id == action.actor_cell_id);
+            if teammate
+                && action.action_type == "pick"
+                && action.completed
+                && action.champion_id != 0
+                && self.locked.insert(action.actor_cell_id)
+            {
+                transitions.push(Transition::TeammateLocked {
+                    cell_id: action.actor_cell_id,
+                    champion_id: action.champion_id,
+                });
+            }
+        }
+
+        for kind in [ActionKind::Ban, ActionKind::Pick] {
+            if let Some(action) = data.local_action_in_progress(kind.as_str())
+                && self.turns.insert(action.id)
+            {
+                transitions.push(Transition::MyTurn {
+                    kind,
+                    action_id: action.id,
+                });
+            }
+        }
+
+        transitions
+    }
+}
+
+#[test]
+fn test_champ_select_transitions() {
+    use crate::fixtures::{self, action};
+    use serde_json::{Value, json};
+
+    let snapshot = |phase: &str, time_left: i64, actions: Value| -> ChampSelectData {
+        fixtures::load(fixtures::CHAMP_SELECT, |session| {
+            session["timer"]["phase"] = phase.into();
+            session["timer"]["adjustedTimeLeftInPhase"] = time_left.into();
+            session["actions"] = actions;
+        })
+    };
+    let mut state = ChampSelectState::default();
+
+    let planning = snapshot("PLANNING", 30000, json!([]));
+    let mut expected = vec![Transition::PhaseChanged(ChampSelectPhase::Planning)];
+    expected.extend(
+        planning
+            .my_team
+            .iter()
+            .filter(|player| player.cell_id != planning.local_player_cell_id)
+            .map(|player| Transition::TeammateJoined {
+                cell_id: player.cell_id,
+                puuid: player.puuid.clone(),
+            }),
+    );
+    assert_eq!(expected.len(), 5);
+    assert_eq!(state.update(&planning), expected);
+
+    let ban = json!([[action(3, 2, "ban", 0, false, true)]]);
+    let transitions = state.update(&snapshot("BAN_PICK", 30000, ban.clone()));
+    assert_eq!(
+        transitions,
+        vec![
+            Transition::PhaseChanged(ChampSelectPhase::BanPick),
+            Transition::MyTurn {
+                kind: ActionKind::Ban,
+                action_id: 3
+            },
+        ]
+    );
+
+    // 同一操作不会重复触发
+    let transitions = state.update(&snapshot("BAN_PICK", 4000, ban));
+    assert_eq!(
+        transitions,
+        vec![
+            Transition::TimerMilestone { seconds_left: 10 },
+            Transition::TimerMilestone { seconds_left: 5 },
+        ]
+    );
+
+    let pick = json!([
+        [action(3, 2, "ban", 0, true, false)],
+        [action(11, 0, "pick", 86, true, false)],
+        [
+            action(14, 1, "pick", 0, false, true),
+            action(15, 2, "pick", 0, false, true)
+        ]
+    ]);
+    let transitions = state.update(&snapshot("BAN_PICK", 30000, pick.clone()));
+    assert_eq!(
+        transitions,
+        vec![
+            Transition::TeammateLocked {
+                cell_id: 0,
+                champion_id: 86
+            },
+            Transition::MyTurn {
+                kind: ActionKind::Pick,
+                action_id: 15
+            },
+        ]
+    );
+    assert_eq!(state.phase(), Some(&ChampSelectPhase::BanPick));
+
+    // 选择失败后，操作仍在进行时再次触发
+    assert!(
+        state
+            .update(&snapshot("BAN_PICK", 29000, pick.clone()))
+            .is_empty()
+    );
+    for _ in 0..MAX_TURN_RETRIES {
+        assert!(state.retry_turn(15));
+        assert_eq!(
+            state.update(&snapshot("BAN_PICK", 28000, pick.clone())),
+            vec![Transition::MyTurn {
+                kind: ActionKind::Pick,
+                action_id: 15
+            }]
+        );
+    }
+    // 重试次数用完后不再触发
+    assert!(!state.retry_turn(15));
+    assert!(state.update(&snapshot("BAN_PICK", 27000, pick)).is_empty());
+}
+
+#[test]
+fn test_champ_select_session_changed() {
+    use crate::fixtures;
+
+    // 有人秒退后直接进入新的英雄选择，会话和游戏ID都会变化
+    let snapshot = |game_id: u64| -> ChampSelectData {
+        fixtures::load(fixtures::CHAMP_SELECT, |session| {
+            session["gameId"] = game_id.into();
+            session["id"] = format!("session-{game_id}").into();
+        })
+    };
+    let mut state = ChampSelectState::default();
+    let first = state.update(&snapshot(1));
+    assert_eq!(
+        first[0],
+        Transition::PhaseChanged(ChampSelectPhase::BanPick)
+    );
+    assert!(first.contains(&Transition::MyTurn {
+        kind: ActionKind::Ban,
+        action_id: 3,
+    }));
+    assert!(state.update(&snapshot(1)).is_empty());
+
+    // 相同的位置和操作ID再次触发
+    let mut second = vec![Transition::SessionChanged];
+    second.extend(first);
+    assert_eq!(state.update(&snapshot(2)), second);
+    assert!(state.update(&snapshot(2)).is_empty());
+}
@@ -1,13 +1,8 @@
-use std::{
-    sync::{LazyLock, atomic::Ordering},
-    time::Duration,
-};
+use std::sync::LazyLock;
 
 use crate::{CONTEXT, LcuClient};
-use log::error;
 use regex::Regex;
 use serde::{Deserialize, Deserializer, de::Error};
-use tokio::time::sleep;
 
 #[derive(Debug, Deserialize, PartialEq, Eq)]
 #[serde(rename_all = "lowercase")]
@@ -78,26 +73,11 @@ where
 }
 
 impl LcuClient {
+    /// 记录英雄选择聊天的ID，战绩分析由英雄选择事件触发
     pub async fn handle_chat_conversation_event(&self, data: ChatConversation) {
-        if !CONTEXT.auto_send_analysis.load(Ordering::Relaxed)
-            || data.data.message_type != MessageType::System
-            || data.data.body != "joined_room"
-            || CONTEXT.game_mode.read().unwrap().is_empty()
-            || *CONTEXT.game_mode.read().unwrap() == "TFT"
-        {
-            return;
+        if data.data.message_type == MessageType::System && data.data.body == "joined_room" {
+            *CONTEXT.champ_select_conversation.write().unwrap() = Some(data.conversation_id);
         }
-        let conversation_id = data.conversation_id;
-        let game_mode = CONTEXT.game_mode.read().unwrap().clone();
-        if let Ok(player_score) = self
-            .analyze_player(&data.data.from_puuid, &game_mode)
-            .await
-            .inspect_err(|e| error!("战绩分析失败: {:?}", e))
-        {
-            self.send_message(&conversation_id, &format!("{player_score}"))
-                .await;
-            sleep(Duration::from_secs(1)).await; // 避免发送消息过快
-        };
     }
 }
 
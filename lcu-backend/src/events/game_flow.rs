@@ -1,9 +1,9 @@
 use std::sync::atomic::Ordering;
 
 use crate::Result;
-use log::info;
+use log::{error, info, warn};
 
-use crate::{CONTEXT, ChampSelectPlayer, LcuClient};
+use crate::{CONTEXT, ChampSelectPlayer, LcuClient, MatchFilter, notes::alert_players};
 use serde::Deserialize;
 
 #[derive(Debug, Deserialize)]
@@ -17,9 +17,18 @@ pub struct GameFlowSession {
 #[derive(Debug, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct GameFlowGameData {
-    // pub game_id: u64,
+    #[serde(default)]
+    pub game_id: u64,
     pub team_one: Vec<ChampSelectPlayer>,
     pub team_two: Vec<ChampSelectPlayer>,
+    #[serde(default)]
+    pub queue: Queue,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Queue {
+    pub id: i32, // 不在队列中时为-1
 }
 
 #[derive(Debug, Deserialize)]
@@ -29,23 +38,30 @@ pub struct Map {
     // pub name: String,
 }
 
-#[derive(Default, Debug, Deserialize, PartialEq, Eq)]
+/// 客户端状态，按一局游戏的先后顺序排列
+#[derive(Default, Debug, Clone, Copy, Deserialize, PartialEq, Eq)]
 pub enum GamePhase {
+    #[default]
+    None,
+    Lobby,
+    Matchmaking,
+    CheckedIntoTournament,
+    ReadyCheck,
     ChampSelect,
     GameStart,
+    FailedToLaunch,
     InProgress,
-    Lobby,
-    Matchmaking,
-    #[default]
-    None,
+    Reconnect,
+    WaitingForStats,
     PreEndOfGame,
-    ReadyCheck,
+    EndOfGame,
+    TerminatedInError,
     #[serde(other)]
     Other,
 }
 
 impl LcuClient {
-    pub(crate) async fn handle_game_flow_event(&self, data: GameFlowSession) -> Result<()> {
+    pub(crate) async fn handle_game_flow_event(&self, mut data: GameFlowSession) -> Result<()> {
         // 在 if 语句中使用 read 锁，避免长时间持有锁导致死锁
         if *CONTEXT.game_phase.read().unwrap() == data.phase {
             return Ok(());
@@ -56,8 +72,24 @@ impl LcuClient {
         {
             info!("当前游戏模式: {}", &data.map.game_mode);
         }
+        if !data.map.game_mode.is_empty()
+            && let Some(name) = CONTEXT
+                .profiles
+                .write()
+                .unwrap()
+                .switch(data.game_data.queue.id, &data.map.game_mode)
+        {
+            info!("切换选择方案: {name}");
+        }
+
+        if data.phase != GamePhase::Reconnect
+            && let Some(task) = CONTEXT.reconnect_task.lock().unwrap().take()
+        {
+            task.abort();
+        }
 
-        match &data.phase {
+        match data.phase {
+            // 英雄选择的会话事件可能早于状态变化到达，只在回到房间时清除上一次的状态
             GamePhase::Lobby | GamePhase::None => {
                 CONTEXT.reset();
             }
@@ -67,11 +99,55 @@ impl LcuClient {
             GamePhase::ReadyCheck => {
                 self.auto_accept().await;
             }
+            GamePhase::CheckedIntoTournament => {
+                info!("已签到冠军杯赛");
+            }
+            GamePhase::FailedToLaunch => {
+                error!("游戏启动失败");
+            }
+            // 加载界面开始分析全部玩家，重新连接时不会重复分析
+            GamePhase::GameStart | GamePhase::InProgress => {
+                let teams = [
+                    std::mem::take(&mut data.game_data.team_one),
+                    std::mem::take(&mut data.game_data.team_two),
+                ];
+                alert_players(
+                    teams.iter().flatten().map(|player| player.puuid.as_str()),
+                    "游戏开始",
+                );
+                let filter = MatchFilter {
+                    queue_id: data.game_data.queue.id,
+                    game_mode: data.map.game_mode.clone(),
+                };
+                self.spawn_ranked_snapshot(data.game_data.game_id);
+                self.spawn_lobby_report(data.game_data.game_id, teams, filter);
+            }
+            GamePhase::Reconnect => {
+                self.spawn_auto_reconnect();
+            }
+            GamePhase::WaitingForStats => {
+                info!("游戏结束，等待结算数据");
+            }
+            // 两个阶段都尝试生成赛后报告，结算数据生成后只处理一次
+            GamePhase::PreEndOfGame => {
+                self.spawn_post_game_report();
+            }
+            GamePhase::EndOfGame => {
+                self.spawn_post_game_report();
+                CONTEXT.reset();
+            }
+            GamePhase::TerminatedInError => {
+                warn!("对局因错误终止");
+                CONTEXT.reset();
+            }
             _ => {}
         }
 
         *CONTEXT.game_phase.write().unwrap() = data.phase;
         *CONTEXT.game_mode.write().unwrap() = data.map.game_mode;
+        CONTEXT
+            .queue_id
+            .store(data.game_data.queue.id, Ordering::Relaxed);
         Ok(())
     }
 }
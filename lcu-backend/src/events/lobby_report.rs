@@ -0,0 +1,223 @@
+use std::{fmt::Display, sync::atomic::Ordering};
+
+use futures_util::{StreamExt, stream};
+use log::{error, info};
+
+use super::{champ_select::ChampSelectPlayer, team_analysis::ANALYSIS_CONCURRENCY};
+use crate::{
+    CONTEXT, LcuClient, MatchFilter, PlayerScore,
+    output::{Feature, append_report},
+};
+
+/// 对局中的一名玩家
+#[derive(Debug)]
+pub struct LobbyPlayer {
+    pub puuid: String,
+    pub champion_id: u16,
+    pub champion_name: String,
+    // 匿名玩家、机器人或查询失败时为None
+    pub score: Option<PlayerScore>,
+}
+
+impl LobbyPlayer {
+    /// 本局英雄在近期对局中的场次和胜场
+    pub fn champion_record(&self) -> (u32, u32) {
+        self.score
+            .as_ref()
+            .and_then(|score| score.champion_usage(self.champion_id))
+            .map_or((0, 0), |usage| (usage.games, usage.wins))
+    }
+
+    /// 单双排段位，未定级时使用灵活排位段位
+    pub fn rank(&self) -> Option<String> {
+        let ranked = self.score.as_ref()?.ranked.as_ref()?;
+        ranked.solo.rank().or_else(|| ranked.flex.rank())
+    }
+}
+
+impl Display for LobbyPlayer {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let Some(score) = &self.score else {
+            return write!(f, "{}: 无战绩数据", self.champion_name);
+        };
+        let (games, wins) = self.champion_record();
+        write!(f, "{} {}", self.champion_name, score.name)?;
+        if let Some(rank) = self.rank() {
+            write!(f, "（{rank}）")?;
+        }
+        write!(
+            f,
+            ": 胜率{}/{}，KDA {:.2}，本英雄{}场{}胜",
+            score.wins, score.total, score.kda, games, wins
+        )?;
+        if let Some(premade) = score.premade {
+            write!(f, "，组队{premade}")?;
+        }
+        if score.repeats.abs() >= 3 {
+            let streak = if score.repeats > 0 {
+                "连胜"
+            } else {
+                "连败"
+            };
+            write!(f, "，{}{}", score.repeats.abs(), streak)?;
+        }
+        if let Some(encounters) = score.encounters {
+            write!(f, "，{encounters}")?;
+        }
+        Ok(())
+    }
+}
+
+/// 游戏开始时的全部玩家分析，只在本地显示
+#[derive(Debug, Default)]
+pub struct LobbyReport {
+    pub game_id: u64,
+    pub allies: Vec<LobbyPlayer>,
+    pub enemies: Vec<LobbyPlayer>,
+}
+
+impl Display for LobbyReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        for (team, players) in [("我方", &self.allies), ("敌方", &self.enemies)] {
+            writeln!(f, "{team}:")?;
+            for player in players {
+                writeln!(f, "  {player}")?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl LcuClient {
+    /// 在后台分析对局中的全部玩家，每局只分析一次
+    pub(crate) fn spawn_lobby_report(
+        &self,
+        game_id: u64,
+        teams: [Vec<ChampSelectPlayer>; 2],
+        filter: MatchFilter,
+    ) {
+        let sinks = CONTEXT.outputs.read().unwrap().sinks(Feature::LobbyReport);
+        // 记录已分析的对局，避免 GameStart 和 InProgress 重复分析
+        if !sinks.any()
+            || game_id == 0
+            || filter.game_mode == "TFT"
+            || CONTEXT.reported_game.swap(game_id, Ordering::Relaxed) == game_id
+        {
+            return;
+        }
+        if sinks.panel {
+            *CONTEXT.lobby_report.write().unwrap() = Some(LobbyReport {
+                game_id,
+                ..Default::default()
+            });
+        }
+        let client = self.clone();
+        tokio::spawn(async move {
+            let report = client.build_lobby_report(game_id, teams, filter).await;
+            if sinks.console {
+                for line in report.to_string().lines() {
+                    info!("{line}");
+                }
+            }
+            if sinks.file {
+                let _ = append_report(Feature::LobbyReport, &report.to_string())
+                    .inspect_err(|e| error!("保存对局报告失败: {e}"));
+            }
+            if sinks.panel {
+                *CONTEXT.lobby_report.write().unwrap() = Some(report);
+            }
+        });
+    }
+
+    async fn build_lobby_report(
+        &self,
+        game_id: u64,
+        teams: [Vec<ChampSelectPlayer>; 2],
+        filter: MatchFilter,
+    ) -> LobbyReport {
+        if CONTEXT.champion_names.read().unwrap().is_empty()
+            && let Ok(names) = self.get_champion_names().await
+        {
+            *CONTEXT.champion_names.write().unwrap() = names;
+        }
+        let me = CONTEXT.me.read().unwrap().puuid.clone();
+        let [team_one, team_two] = teams;
+        let (allies, enemies) = if team_two.iter().any(|player| player.puuid == me) {
+            (team_two, team_one)
+        } else {
+            (team_one, team_two)
+        };
+        let ally_count = allies.len();
+
+        let mut players = stream::iter(allies.into_iter().chain(enemies))
+            .map(|player| {
+                let filter = &filter;
+                async move {
+                    let score = if player.puuid.is_empty() {
+                        None
+                    } else {
+                        self.analyze_player(&player.puuid, filter)
+                            .await
+                            .inspect_err(|e| error!("战绩分析失败: {:?}", e))
+                            .ok()
+                    };
+                    let champion_name = CONTEXT
+                        .champion_names
+                        .read()
+                        .unwrap()
+                        .get(&player.champion_id)
+                        .cloned()
+                        .unwrap_or_else(|| player.champion_id.to_string());
+                    LobbyPlayer {
+                        puuid: player.puuid,
+                        champion_id: player.champion_id,
+                        champion_name,
+                        score,
+                    }
+                }
+            })
+            .buffered(ANALYSIS_CONCURRENCY)
+            .collect::<Vec<_>>()
+            .await;
+        let puuids = players
+            .iter()
+            .filter(|player| player.score.is_some())
+            .map(|player| player.puuid.clone())
+            .collect::<Vec<_>>();
+        let premades = self.detect_premades(&puuids).await;
+        self.refresh_encounter_index().await;
+        {
+            let index = CONTEXT.encounter_index.read().unwrap();
+            for player in &mut players {
+                if let Some(score) = &mut player.score {
+                    score.premade = premades.get(&player.puuid).copied();
+                    score.encounters = index.summary(&player.puuid);
+                }
+            }
+        }
+        let enemies = players.split_off(ally_count);
+        LobbyReport {
+            game_id,
+            allies: players,
+            enemies,
+        }
+    }
+}
+
+#[test]
+fn test_lobby_player_display() {
+    let mut player = LobbyPlayer {
+        puuid: "puuid".to_string(),
+        champion_id: 103,
+        champion_name: "阿狸".to_string(),
+        score: None,
+    };
+    assert_eq!(player.to_string(), "阿狸: 无战绩数据");
+
+    player.score = Some(PlayerScore::sample());
+    assert_eq!(player.champion_record(), (8, 5));
+    assert_eq!(
+        player.to_string(),
+        "阿狸 示例玩家#1234（黄金 II 45LP）: 胜率12/20，KDA 3.25，本英雄8场5胜，组队1，3连胜，近期遇到过3次（队友1胜1负，对手1胜0负）"
+    );
+}
@@ -1,8 +1,12 @@
 pub mod champ_select;
+pub mod champ_select_state;
 pub mod chat;
 pub mod game_flow;
+pub mod lobby_report;
 pub mod matchmaking;
+pub mod post_game;
 pub mod process_control;
+mod team_analysis;
 
 use serde::Deserialize;
 use serde_json::Value;
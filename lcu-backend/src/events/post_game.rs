@@ -0,0 +1,536 @@
+use std::{
+    collections::HashMap,
+    fmt::{Display, Write},
+    path::PathBuf,
+    sync::atomic::Ordering,
+};
+
+use log::{debug, error, info, warn};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::{
+    CONTEXT, LcuClient, Result,
+    library::data_dir,
+    output::{Feature, REPORTS, append_report},
+    store::{PlayerRollup, STORE},
+};
+
+// 计算近期平均值使用的对局数
+const ROLLING_GAMES: usize = 20;
+// 连胜、连败达到该场次时在报告中提示
+const NOTABLE_STREAK: usize = 3;
+// 最大连杀达到该数量时在报告中提示
+const NOTABLE_KILLING_SPREE: u32 = 5;
+// Markdown 报告所在的目录，位于报告目录中
+const MARKDOWN_DIR: &str = "post_game";
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct EogPlayer {
+    #[serde(default)]
+    team_id: u16,
+    #[serde(default)]
+    champion_id: u16,
+    // CHAMPIONS_KILLED、NUM_DEATHS 等统计项
+    #[serde(default)]
+    stats: HashMap<String, Value>,
+}
+
+impl EogPlayer {
+    fn stat(&self, key: &str) -> u32 {
+        self.stats
+            .get(key)
+            .and_then(Value::as_f64)
+            .map_or(0, |value| value as u32)
+    }
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct EogTeam {
+    team_id: u16,
+    #[serde(default)]
+    is_winning_team: bool,
+    #[serde(default)]
+    players: Vec<EogPlayer>,
+}
+
+/// 结算数据，对应 /lol-end-of-game/v1/eog-stats-block
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct EogStatsBlock {
+    game_id: u64,
+    game_length: u32, // 秒
+    #[serde(default)]
+    game_mode: String,
+    // RANKED_SOLO_5x5、RANKED_FLEX_SR 等
+    #[serde(default)]
+    queue_type: String,
+    local_player: EogPlayer,
+    #[serde(default)]
+    teams: Vec<EogTeam>,
+}
+
+impl EogStatsBlock {
+    /// 根据近期汇总和之前的胜负生成报告，英雄名称和段位变化由调用方填充
+    fn report(&self, queue_id: i32, averages: &PlayerRollup, previous: &[bool]) -> PostGameReport {
+        let me = &self.local_player;
+        let team = self.teams.iter().find(|team| team.team_id == me.team_id);
+        let win = team.is_some_and(|team| team.is_winning_team);
+        let (kills, deaths, assists) = (
+            me.stat("CHAMPIONS_KILLED"),
+            me.stat("NUM_DEATHS"),
+            me.stat("ASSISTS"),
+        );
+        let damage = me.stat("TOTAL_DAMAGE_DEALT_TO_CHAMPIONS");
+        let cs = me.stat("MINIONS_KILLED") + me.stat("NEUTRAL_MINIONS_KILLED");
+
+        let per_minute = |value: f32, seconds: f32| {
+            if seconds > 0.0 {
+                value / seconds * 60.0
+            } else {
+                0.0
+            }
+        };
+        let (duration, average_duration) = (self.game_length as f32, averages.duration as f32);
+        let average = |value: f32| (averages.games > 0).then_some(value);
+        let stats = vec![
+            StatComparison::new(
+                "KDA",
+                (kills + assists) as f32 / (deaths + 1) as f32,
+                average((averages.kills + averages.assists) as f32 / (averages.deaths + 1) as f32),
+            ),
+            StatComparison::new(
+                "分均伤害",
+                per_minute(damage as f32, duration),
+                average(per_minute(averages.damage as f32, average_duration)),
+            ),
+            StatComparison::new(
+                "分均补刀",
+                per_minute(cs as f32, duration),
+                average(per_minute(averages.cs as f32, average_duration)),
+            ),
+            StatComparison::new(
+                "分均经济",
+                per_minute(me.stat("GOLD_EARNED") as f32, duration),
+                average(per_minute(averages.gold as f32, average_duration)),
+            ),
+            StatComparison::new(
+                "视野得分",
+                me.stat("VISION_SCORE") as f32,
+                average(averages.vision as f32 / averages.games.max(1) as f32),
+            ),
+        ];
+
+        let damage_share = team.and_then(|team| {
+            let total = team
+                .players
+                .iter()
+                .map(|player| player.stat("TOTAL_DAMAGE_DEALT_TO_CHAMPIONS"))
+                .sum::<u32>();
+            (total > 0).then(|| damage as f32 / total as f32 * 100.0)
+        });
+
+        let mut streaks = vec![];
+        let streak = 1 + previous.iter().take_while(|&&result| result == win).count();
+        if streak >= NOTABLE_STREAK {
+            streaks.push(format!("{streak}{}", if win { "连胜" } else { "连败" }));
+        }
+        let killing_spree = me.stat("LARGEST_KILLING_SPREE");
+        if killing_spree >= NOTABLE_KILLING_SPREE {
+            streaks.push(format!("最大连杀{killing_spree}"));
+        }
+
+        PostGameReport {
+            game_id: self.game_id,
+            created_at: 0,
+            queue_id,
+            game_mode: self.game_mode.clone(),
+            champion_id: me.champion_id,
+            champion_name: String::new(),
+            win,
+            duration: self.game_length,
+            kills,
+            deaths,
+            assists,
+            average_games: averages.games,
+            stats,
+            damage_share,
+            rank_change: None,
+            streaks,
+        }
+    }
+}
+
+/// 本局数据和近期平均值的对比
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StatComparison {
+    pub name: String,
+    pub value: f32,
+    // 本地没有同队列的近期对局时为None
+    pub average: Option<f32>,
+}
+
+impl StatComparison {
+    fn new(name: &str, value: f32, average: Option<f32>) -> Self {
+        Self {
+            name: name.to_string(),
+            value,
+            average,
+        }
+    }
+
+    /// 相对近期平均值的变化百分比
+    pub fn change(&self) -> Option<f32> {
+        self.average
+            .filter(|average| *average > 0.0)
+            .map(|average| (self.value - average) / average * 100.0)
+    }
+}
+
+impl Display for StatComparison {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{} {:.1}", self.name, self.value)?;
+        match (self.average, self.change()) {
+            (Some(average), Some(change)) => write!(f, "（平均 {average:.1}，{change:+.0}%）"),
+            (Some(average), None) => write!(f, "（平均 {average:.1}）"),
+            _ => Ok(()),
+        }
+    }
+}
+
+/// 游戏结束后自己的数据总结，保存在本地数据库中
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PostGameReport {
+    pub game_id: u64,
+    pub created_at: i64, // 生成报告的时间，毫秒
+    pub queue_id: i32,
+    pub game_mode: String,
+    pub champion_id: u16,
+    pub champion_name: String,
+    pub win: bool,
+    pub duration: u32, // 秒
+    pub kills: u32,
+    pub deaths: u32,
+    pub assists: u32,
+    // 计算平均值使用的同队列对局数
+    pub average_games: u32,
+    pub stats: Vec<StatComparison>,
+    pub damage_share: Option<f32>,   // 占己方英雄伤害的百分比
+    pub rank_change: Option<String>, // 排位队列的段位和胜点变化
+    pub streaks: Vec<String>,        // 值得注意的连胜、连败和连杀
+}
+
+impl PostGameReport {
+    /// 报告标题，如 "2025-01-01 20:00 阿狸 胜利"
+    pub fn title(&self) -> String {
+        let time = chrono::DateTime::from_timestamp_millis(self.created_at)
+            .map(|time| {
+                time.with_timezone(&chrono::Local)
+                    .format("%Y-%m-%d %H:%M")
+                    .to_string()
+            })
+            .unwrap_or_default();
+        let result = if self.win { "胜利" } else { "失败" };
+        format!("{time} {} {result}", self.champion_name)
+    }
+
+    pub fn to_markdown(&self) -> String {
+        let mut md = String::new();
+        let _ = writeln!(md, "# {}\n", self.title());
+        let _ = writeln!(
+            md,
+            "- 模式：{}（队列 {}），时长 {} 分钟",
+            self.game_mode,
+            self.queue_id,
+            self.duration / 60
+        );
+        let _ = writeln!(
+            md,
+            "- 战绩：{}/{}/{}",
+            self.kills, self.deaths, self.assists
+        );
+        if let Some(share) = self.damage_share {
+            let _ = writeln!(md, "- 伤害占比：{share:.1}%");
+        }
+        if let Some(rank) = &self.rank_change {
+            let _ = writeln!(md, "- 段位：{rank}");
+        }
+        if !self.streaks.is_empty() {
+            let _ = writeln!(md, "- 亮点：{}", self.streaks.join("，"));
+        }
+        let _ = writeln!(
+            md,
+            "\n| 数据 | 本局 | 近期{}场平均 | 变化 |\n| --- | --- | --- | --- |",
+            self.average_games
+        );
+        for stat in &self.stats {
+            let _ = writeln!(
+                md,
+                "| {} | {:.1} | {} | {} |",
+                stat.name,
+                stat.value,
+                stat.average
+                    .map_or("-".to_string(), |average| format!("{average:.1}")),
+                stat.change()
+                    .map_or("-".to_string(), |change| format!("{change:+.0}%"))
+            );
+        }
+        md
+    }
+
+    /// 导出为数据目录中的 Markdown 文件，返回文件路径
+    pub fn export_markdown(&self) -> Result<PathBuf> {
+        let dir = data_dir().join(REPORTS).join(MARKDOWN_DIR);
+        std::fs::create_dir_all(&dir)?;
+        let path = dir.join(format!("{}.md", self.game_id));
+        std::fs::write(&path, self.to_markdown())?;
+        Ok(path)
+    }
+
+    /// 本地保存的最近 limit 份报告，按时间从新到旧
+    pub fn history(limit: usize) -> Result<Vec<PostGameReport>> {
+        Ok(STORE
+            .load_reports(limit)?
+            .into_iter()
+            .filter_map(|data| {
+                serde_json::from_str(&data)
+                    .inspect_err(|e| warn!("读取赛后报告失败: {e}"))
+                    .ok()
+            })
+            .collect())
+    }
+}
+
+impl Display for PostGameReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        writeln!(
+            f,
+            "{} {} {}分钟 {}/{}/{}",
+            self.champion_name,
+            if self.win { "胜利" } else { "失败" },
+            self.duration / 60,
+            self.kills,
+            self.deaths,
+            self.assists
+        )?;
+        if self.average_games == 0 {
+            writeln!(f, "本地没有同队列的近期对局，无法对比平均值")?;
+        } else {
+            writeln!(f, "对比近期{}场同队列对局：", self.average_games)?;
+        }
+        for stat in &self.stats {
+            writeln!(f, "{stat}")?;
+        }
+        if let Some(share) = self.damage_share {
+            writeln!(f, "伤害占比 {share:.1}%")?;
+        }
+        if let Some(rank) = &self.rank_change {
+            writeln!(f, "段位 {rank}")?;
+        }
+        if !self.streaks.is_empty() {
+            writeln!(f, "{}", self.streaks.join("，"))?;
+        }
+        Ok(())
+    }
+}
+
+impl LcuClient {
+    /// 游戏开始时记录自己的段位，用于计算赛后的胜点变化
+    pub(crate) fn spawn_ranked_snapshot(&self, game_id: u64) {
+        let me = CONTEXT.me.read().unwrap().puuid.clone();
+        if game_id == 0
+            || me.is_empty()
+            || CONTEXT
+                .ranked_before
+                .read()
+                .unwrap()
+                .as_ref()
+                .is_some_and(|(id, _)| *id == game_id)
+        {
+            return;
+        }
+        let client = self.clone();
+        tokio::spawn(async move {
+            match client.fetch_ranked_stats(&me).await {
+                Ok(stats) => *CONTEXT.ranked_before.write().unwrap() = Some((game_id, stats)),
+                Err(e) => warn!("查询段位失败: {e}"),
+            }
+        });
+    }
+
+    /// 在后台生成赛后报告，PreEndOfGame 和 EndOfGame 都会触发，每局只生成一次
+    pub(crate) fn spawn_post_game_report(&self) {
+        let sinks = CONTEXT
+            .outputs
+            .read()
+            .unwrap()
+            .sinks(Feature::PostGameReport);
+        if !sinks.any() {
+            return;
+        }
+        let queue_id = CONTEXT.queue_id.load(Ordering::Relaxed);
+        let client = self.clone();
+        tokio::spawn(async move {
+            let report = match client.build_post_game_report(queue_id).await {
+                Ok(Some(report)) => report,
+                Ok(None) => return,
+                // PreEndOfGame 时结算数据可能还没有生成，EndOfGame 时会再次尝试
+                Err(e) => {
+                    debug!("生成赛后报告失败: {e}");
+                    return;
+                }
+            };
+            if CONTEXT.export_markdown.load(Ordering::Relaxed) {
+                match report.export_markdown() {
+                    Ok(path) => info!("赛后报告已导出到 {}", path.display()),
+                    Err(e) => error!("导出赛后报告失败: {e}"),
+                }
+            }
+            if sinks.console {
+                for line in report.to_string().lines() {
+                    info!("{line}");
+                }
+            }
+            if sinks.file {
+                let _ = append_report(Feature::PostGameReport, &report.to_string())
+                    .inspect_err(|e| error!("保存赛后报告失败: {e}"));
+            }
+            if sinks.panel {
+                *CONTEXT.post_game_report.write().unwrap() = Some(report);
+            }
+        });
+    }
+
+    async fn build_post_game_report(&self, queue_id: i32) -> Result<Option<PostGameReport>> {
+        let eog = self
+            .get("/lol-end-of-game/v1/eog-stats-block")
+            .await?
+            .json::<EogStatsBlock>()
+            .await?;
+        if eog.game_mode == "TFT"
+            || CONTEXT
+                .post_game_reported
+                .swap(eog.game_id, Ordering::Relaxed)
+                == eog.game_id
+        {
+            return Ok(None);
+        }
+        let me = CONTEXT.me.read().unwrap().puuid.clone();
+        // 平均值只使用本地数据库中的对局，先保存最近一页战绩
+        if let Err(e) = self.get_matches(&me, 0, ROLLING_GAMES).await {
+            warn!("查询近期对局失败: {e}");
+        }
+        let averages = STORE
+            .recent_rollup(&me, queue_id, eog.game_id, ROLLING_GAMES)
+            .inspect_err(|e| warn!("读取近期对局失败: {e}"))
+            .unwrap_or_default();
+        let previous = STORE
+            .recent_results(&me, queue_id, eog.game_id, ROLLING_GAMES)
+            .inspect_err(|e| warn!("读取近期对局失败: {e}"))
+            .unwrap_or_default();
+        let mut report = eog.report(queue_id, &averages, &previous);
+        report.created_at = chrono::Utc::now().timestamp_millis();
+
+        if CONTEXT.champion_names.read().unwrap().is_empty()
+            && let Ok(names) = self.get_champion_names().await
+        {
+            *CONTEXT.champion_names.write().unwrap() = names;
+        }
+        report.champion_name = CONTEXT
+            .champion_names
+            .read()
+            .unwrap()
+            .get(&report.champion_id)
+            .cloned()
+            .unwrap_or_else(|| report.champion_id.to_string());
+
+        let before = CONTEXT
+            .ranked_before
+            .read()
+            .unwrap()
+            .clone()
+            .filter(|(game_id, _)| *game_id == eog.game_id);
+        if let Some((_, before)) = before
+            && let Some(before) = before.queue(&eog.queue_type)
+        {
+            report.rank_change = self
+                .fetch_ranked_stats(&me)
+                .await
+                .inspect_err(|e| warn!("查询段位失败: {e}"))
+                .ok()
+                .and_then(|after| after.queue(&eog.queue_type)?.change_since(before));
+        }
+
+        if let Err(e) = STORE.insert_report(
+            report.game_id,
+            report.created_at,
+            &serde_json::to_string(&report)?,
+        ) {
+            warn!("保存赛后报告失败: {e}");
+        }
+        Ok(Some(report))
+    }
+}
+
+#[test]
+fn test_post_game_report() {
+    let json = r#"{
+        "gameId": 1, "gameLength": 1800, "gameMode": "CLASSIC", "queueType": "RANKED_SOLO_5x5",
+        "localPlayer": {
+            "teamId": 100, "championId": 103,
+            "stats": {
+                "CHAMPIONS_KILLED": 10, "NUM_DEATHS": 2, "ASSISTS": 8,
+                "TOTAL_DAMAGE_DEALT_TO_CHAMPIONS": 30000, "GOLD_EARNED": 15000,
+                "MINIONS_KILLED": 200, "NEUTRAL_MINIONS_KILLED": 10, "VISION_SCORE": 25,
+                "LARGEST_KILLING_SPREE": 6, "WIN": 1.0
+            }
+        },
+        "teams": [
+            {"teamId": 100, "isWinningTeam": true, "players": [
+                {"stats": {"TOTAL_DAMAGE_DEALT_TO_CHAMPIONS": 30000}},
+                {"stats": {"TOTAL_DAMAGE_DEALT_TO_CHAMPIONS": 70000}}
+            ]},
+            {"teamId": 200, "isWinningTeam": false, "players": []}
+        ]
+    }"#;
+    let eog: EogStatsBlock = serde_json::from_str(json).expect("Deserialization failed");
+    let averages = PlayerRollup {
+        games: 2,
+        kills: 10,
+        deaths: 4,
+        assists: 10,
+        damage: 60000,
+        gold: 30000,
+        cs: 420,
+        vision: 40,
+        duration: 3600,
+        ..Default::default()
+    };
+    let report = eog.report(420, &averages, &[true, true, false]);
+    assert!(report.win);
+    assert_eq!((report.kills, report.deaths, report.assists), (10, 2, 8));
+    assert_eq!(report.damage_share.map(f32::round), Some(30.0));
+    assert_eq!(report.streaks, vec!["3连胜", "最大连杀6"]);
+    assert_eq!(report.stats[0].to_string(), "KDA 6.0（平均 4.0，+50%）");
+    assert_eq!(
+        report.stats[1].to_string(),
+        "分均伤害 1000.0（平均 1000.0，+0%）"
+    );
+    assert_eq!(
+        report.stats[4].to_string(),
+        "视野得分 25.0（平均 20.0，+25%）"
+    );
+    assert!(
+        report
+            .to_markdown()
+            .contains("| 分均补刀 | 7.0 | 7.0 | +0% |")
+    );
+
+    // 没有近期对局时不对比平均值
+    let report = eog.report(420, &PlayerRollup::default(), &[]);
+    assert_eq!(report.streaks, vec!["最大连杀6"]);
+    assert_eq!(report.stats[0].to_string(), "KDA 6.0");
+    assert!(report.to_string().contains("无法对比平均值"));
+}
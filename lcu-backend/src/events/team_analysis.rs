@@ -0,0 +1,108 @@
+use std::{sync::atomic::Ordering, time::Duration};
+
+use futures_util::{StreamExt, stream};
+use log::{error, info};
+use tokio::time::sleep;
+
+use crate::{
+    CONTEXT, LcuClient, MatchFilter, PlayerScore,
+    output::{Feature, Sinks, append_report},
+};
+
+// 同时分析的玩家数，避免短时间内大量请求战绩接口
+pub(super) const ANALYSIS_CONCURRENCY: usize = 2;
+
+impl LcuClient {
+    /// 在后台分析新加入的队友，不阻塞英雄选择事件的处理，
+    /// 任务在清除英雄选择状态时取消，避免上一次英雄选择的结果混入
+    pub(crate) fn spawn_team_analysis(&self, puuids: Vec<String>) {
+        let sinks = CONTEXT.outputs.read().unwrap().sinks(Feature::TeamAnalysis);
+        if !sinks.any() || puuids.is_empty() {
+            return;
+        }
+        let game_mode = CONTEXT.game_mode.read().unwrap().clone();
+        if game_mode.is_empty() || game_mode == "TFT" {
+            return;
+        }
+        let filter = MatchFilter {
+            queue_id: CONTEXT.queue_id.load(Ordering::Relaxed),
+            game_mode,
+        };
+        let client = self.clone();
+        let task = tokio::spawn(async move { client.analyze_team(puuids, filter, sinks).await });
+        let mut tasks = CONTEXT.analysis_tasks.lock().unwrap();
+        tasks.retain(|task| !task.is_finished());
+        tasks.push(task.abort_handle());
+    }
+
+    async fn analyze_team(&self, puuids: Vec<String>, filter: MatchFilter, sinks: Sinks) {
+        // 自己也参与组队检测
+        let mut premade_puuids = puuids.clone();
+        premade_puuids.push(CONTEXT.me.read().unwrap().puuid.clone());
+        // 按队伍顺序输出结果
+        let results = stream::iter(puuids)
+            .map(|puuid| {
+                let filter = &filter;
+                async move { self.analyze_player(&puuid, filter).await }
+            })
+            .buffered(ANALYSIS_CONCURRENCY)
+            .collect::<Vec<_>>()
+            .await;
+        let mut scores = results
+            .into_iter()
+            .filter_map(|result| result.inspect_err(|e| error!("战绩分析失败: {:?}", e)).ok())
+            .collect::<Vec<PlayerScore>>();
+        if scores.is_empty() {
+            return;
+        }
+        let premades = self.detect_premades(&premade_puuids).await;
+        self.refresh_encounter_index().await;
+        {
+            let index = CONTEXT.encounter_index.read().unwrap();
+            for score in &mut scores {
+                score.premade = premades.get(&score.puuid).copied();
+                score.encounters = index.summary(&score.puuid);
+            }
+        }
+
+        let messages = {
+            let templates = CONTEXT.analysis_templates.read().unwrap();
+            scores
+                .iter()
+                .map(|score| templates.render(score))
+                .collect::<Vec<_>>()
+        };
+        if sinks.console {
+            for message in &messages {
+                info!("{message}");
+            }
+        }
+        if sinks.file {
+            let _ = append_report(Feature::TeamAnalysis, &messages.join("\n"))
+                .inspect_err(|e| error!("保存队友分析失败: {e}"));
+        }
+        if sinks.chat {
+            self.send_analysis_messages(&messages).await;
+        }
+        if sinks.panel {
+            CONTEXT.team_analysis.write().unwrap().extend(scores);
+        }
+    }
+
+    async fn send_analysis_messages(&self, messages: &[String]) {
+        let Some(conversation_id) = self.get_champ_select_conversation_id().await else {
+            error!("未找到英雄选择聊天，无法发送战绩分析");
+            return;
+        };
+        if CONTEXT.combine_analysis.load(Ordering::Relaxed) {
+            self.send_message(&conversation_id, &messages.join("\n"))
+                .await;
+        } else {
+            for message in messages {
+                self.send_message(&conversation_id, message).await;
+                sleep(Duration::from_secs(1)).await; // 避免发送消息过快
+            }
+        }
+        info!("已发送{}名队友的战绩分析", messages.len());
+    }
+}
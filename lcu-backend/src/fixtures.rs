@@ -0,0 +1,43 @@
+//! 测试使用的客户端数据快照，位于 tests/fixtures，测试中只修改需要的字段
+
+use serde::de::DeserializeOwned;
+use serde_json::{Value, json};
+
+/// 单双排禁用阶段开始时的英雄选择会话，自己在 2 号位置（中单），同时禁用、依次选择
+pub(crate) const CHAMP_SELECT: &str = include_str!("../tests/fixtures/champ_select.json");
+/// 战绩列表中的一局，只包含查询的玩家
+pub(crate) const MATCH: &str = include_str!("../tests/fixtures/match.json");
+/// 同一局的完整对局详情，包含全部玩家
+pub(crate) const GAME: &str = include_str!("../tests/fixtures/game.json");
+
+/// 读取快照，修改后反序列化
+pub(crate) fn load<T: DeserializeOwned>(fixture: &str, edit: impl FnOnce(&mut Value)) -> T {
+    let mut value = serde_json::from_str(fixture).expect("Invalid fixture");
+    edit(&mut value);
+    serde_json::from_value(value).expect("Deserialization failed")
+}
+
+/// 英雄选择中的一个操作
+pub(crate) fn action(
+    id: u8,
+    actor_cell_id: u8,
+    action_type: &str,
+    champion_id: u16,
+    completed: bool,
+    in_progress: bool,
+) -> Value {
+    json!({
+        "actorCellId": actor_cell_id,
+        "championId": champion_id,
+        "completed": completed,
+        "id": id,
+        "isAllyAction": actor_cell_id < 5,
+        "isInProgress": in_progress,
+        "type": action_type,
+    })
+}
+
+/// 战绩列表接口返回的一页对局
+pub(crate) fn match_page(games: impl IntoIterator<Item = Value>) -> Value {
+    json!({"games": {"games": games.into_iter().collect::<Vec<_>>()}})
+}
@@ -4,12 +4,37 @@ mod context;
 mod errors;
 mod event_listener;
 mod events;
+#[cfg(test)]
+mod fixtures;
+pub mod library;
 mod meta;
+pub mod notes;
+pub mod output;
+pub mod store;
+pub mod template;
 
+pub use api::{
+    encounters::{Encounter, EncounterIndex, EncounterSummary},
+    item_sets::ItemSet,
+    match_history::{
+        ChampionUsage, DEFAULT_ANALYSIS_GAMES, MatchFilter, MatchSummary, PlayerScore,
+    },
+    perks::{PerkPage, RunePage},
+    ranked::{RankedQueue, RankedStats},
+};
 pub use client::{LcuClient, default_client};
-pub use context::CONTEXT;
+pub use context::{
+    CONTEXT, Champion, Loadout, LoadoutTarget, LockMode, PickProfile, PickProfiles, Position,
+    SUMMONER_SPELLS, SkinStrategy, SpellPreset, SwapRule, SwapRules, spell_id, spell_name,
+};
 pub use errors::{LcuError, Result};
 pub use event_listener::start_event_listener;
 pub use events::Event;
-pub use events::{champ_select::ChampSelectPlayer, game_flow::GamePhase};
+pub use events::{
+    champ_select::{ChampSelectPlayer, SkipReason, SwapKind},
+    champ_select_state::{ActionKind, ChampSelectPhase, Transition},
+    game_flow::GamePhase,
+    lobby_report::{LobbyPlayer, LobbyReport},
+    post_game::{PostGameReport, StatComparison},
+};
 pub use meta::LcuMeta;
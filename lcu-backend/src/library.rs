@@ -0,0 +1,81 @@
+//! 本地数据库，保存在程序所在目录的 data 文件夹中，每类数据一个子目录，
+//! 每个文件一条记录，支持 JSON 和 TOML 格式
+
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use log::{error, warn};
+use serde::{Serialize, de::DeserializeOwned};
+
+use crate::{LcuError, Result};
+
+// 符文页
+pub const RUNES: &str = "runes";
+// 出装方案
+pub const ITEM_SETS: &str = "item_sets";
+
+/// 助手写入客户端的符文页和出装方案名称前缀，只会覆盖带有该前缀的数据
+pub const MANAGED_PREFIX: &str = "[LH]";
+
+/// 数据目录，无法获取程序路径时使用当前目录
+pub fn data_dir() -> PathBuf {
+    std::env::current_exe()
+        .ok()
+        .and_then(|exe| exe.parent().map(Path::to_path_buf))
+        .unwrap_or_default()
+        .join("data")
+}
+
+fn read_entry<T: DeserializeOwned>(path: &Path) -> Result<T> {
+    let text = fs::read_to_string(path)?;
+    match path.extension().and_then(|ext| ext.to_str()) {
+        Some("toml") => toml::from_str(&text).map_err(|e| LcuError::LibraryError(e.to_string())),
+        _ => Ok(serde_json::from_str(&text)?),
+    }
+}
+
+/// 按文件名顺序读取指定类型的全部记录，无法解析的文件会被跳过
+pub fn load<T: DeserializeOwned>(kind: &str) -> Vec<T> {
+    let Ok(entries) = fs::read_dir(data_dir().join(kind)) else {
+        return vec![];
+    };
+    let mut paths = entries
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| {
+            matches!(
+                path.extension().and_then(|ext| ext.to_str()),
+                Some("json" | "toml")
+            )
+        })
+        .collect::<Vec<_>>();
+    paths.sort();
+    paths
+        .iter()
+        .filter_map(|path| {
+            read_entry(path)
+                .inspect_err(|e| warn!("读取 {} 失败: {e}", path.display()))
+                .ok()
+        })
+        .collect()
+}
+
+/// 以JSON格式保存一条记录，同名文件会被覆盖
+pub fn save<T: Serialize>(kind: &str, name: &str, value: &T) -> Result<PathBuf> {
+    let dir = data_dir().join(kind);
+    fs::create_dir_all(&dir)?;
+    // 替换文件名中不允许的字符
+    let name = name
+        .chars()
+        .map(|c| match c {
+            '<' | '>' | ':' | '"' | '/' | '\\' | '|' | '?' | '*' => '_',
+            c => c,
+        })
+        .collect::<String>();
+    let path = dir.join(format!("{}.json", name.trim()));
+    fs::write(&path, serde_json::to_string_pretty(value)?).inspect_err(|e| {
+        error!("保存 {} 失败: {e}", path.display());
+    })?;
+    Ok(path)
+}
@@ -31,7 +31,7 @@ const CREATE_NO_WINDOW: u32 = 0x08000000;
 const PROCESS_COMMAND_LINE_INFORMATION: u32 = 60;
 const LCU_PROCESS_NAME: &str = "LeagueClientUx.exe";
 
-#[derive(Debug, Default)]
+#[derive(Debug, Default, Clone)]
 pub struct LcuMeta {
     pid: u32,
     pub port: u16,
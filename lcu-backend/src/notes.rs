@@ -0,0 +1,283 @@
+//! 玩家备注、标签和黑名单，标记过的玩家出现在英雄选择或对局中时提醒
+
+use std::{
+    collections::{BTreeMap, HashSet},
+    fmt::Display,
+    path::{Path, PathBuf},
+    sync::{LazyLock, Mutex},
+};
+
+use log::{error, info, warn};
+use serde::{Deserialize, Serialize};
+
+use crate::{CONTEXT, Result, library::data_dir};
+
+// 备注文件名，位于数据目录中。备注是用户自己的数据，与对局缓存数据库分开保存，清理缓存不会影响备注
+pub const NOTES_FILE: &str = "player_notes.json";
+
+pub static NOTES: LazyLock<PlayerNotes> =
+    LazyLock::new(|| PlayerNotes::open(data_dir().join(NOTES_FILE)));
+
+/// 对一名玩家的备注，导入导出时使用 JSON 数组
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PlayerNote {
+    pub puuid: String,
+    pub name: String, // 添加时的玩家名称，只用于显示
+    pub tags: Vec<String>,
+    pub note: String,
+    // 黑名单中的玩家使用警告级别提醒
+    pub blocked: bool,
+}
+
+impl PlayerNote {
+    /// 解析逗号分隔的标签，去掉空白和重复的标签
+    pub fn parse_tags(text: &str) -> Vec<String> {
+        let mut tags = vec![];
+        for tag in text.split([',', '，']).map(str::trim) {
+            if !tag.is_empty() && !tags.iter().any(|t| t == tag) {
+                tags.push(tag.to_string());
+            }
+        }
+        tags
+    }
+
+    /// 合并导入的备注：标签取并集，备注为空时使用导入的备注，任一方在黑名单中则保留在黑名单中
+    pub fn merge(&mut self, other: PlayerNote) {
+        if self.name.is_empty() {
+            self.name = other.name;
+        }
+        for tag in other.tags {
+            if !self.tags.contains(&tag) {
+                self.tags.push(tag);
+            }
+        }
+        if self.note.is_empty() {
+            self.note = other.note;
+        }
+        self.blocked |= other.blocked;
+    }
+}
+
+impl Display for PlayerNote {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let name = if self.name.is_empty() {
+            &self.puuid
+        } else {
+            &self.name
+        };
+        if self.blocked {
+            write!(f, "黑名单玩家 {name}")?;
+        } else {
+            write!(f, "标记玩家 {name}")?;
+        }
+        if !self.tags.is_empty() {
+            write!(f, "（{}）", self.tags.join("、"))?;
+        }
+        if !self.note.is_empty() {
+            write!(f, "：{}", self.note)?;
+        }
+        Ok(())
+    }
+}
+
+/// 全部玩家备注，按 puuid 保存，每次修改后写入备注文件
+pub struct PlayerNotes {
+    path: Option<PathBuf>,
+    notes: Mutex<BTreeMap<String, PlayerNote>>,
+}
+
+impl PlayerNotes {
+    /// 读取备注文件，文件不存在时为空，无法解析时备份原文件后为空
+    pub fn open(path: PathBuf) -> Self {
+        let notes = match std::fs::read_to_string(&path) {
+            Ok(text) => match serde_json::from_str::<Vec<PlayerNote>>(&text) {
+                Ok(notes) => notes,
+                Err(e) => {
+                    error!("解析玩家备注文件失败，已备份为 .bak: {e}");
+                    let _ = std::fs::rename(&path, path.with_extension("json.bak"));
+                    vec![]
+                }
+            },
+            Err(_) => vec![],
+        };
+        Self {
+            path: Some(path),
+            notes: Mutex::new(
+                notes
+                    .into_iter()
+                    .map(|note| (note.puuid.clone(), note))
+                    .collect(),
+            ),
+        }
+    }
+
+    /// 不写入文件的备注，用于测试
+    pub fn in_memory() -> Self {
+        Self {
+            path: None,
+            notes: Mutex::default(),
+        }
+    }
+
+    /// 全部玩家备注，按名称排序
+    pub fn all(&self) -> Vec<PlayerNote> {
+        let mut notes = self
+            .notes
+            .lock()
+            .unwrap()
+            .values()
+            .cloned()
+            .collect::<Vec<_>>();
+        notes.sort_by(|a, b| (&a.name, &a.puuid).cmp(&(&b.name, &b.puuid)));
+        notes
+    }
+
+    pub fn get(&self, puuid: &str) -> Option<PlayerNote> {
+        self.notes.lock().unwrap().get(puuid).cloned()
+    }
+
+    /// 保存玩家备注，已有的备注会被覆盖
+    pub fn save(&self, note: PlayerNote) -> Result<()> {
+        let mut notes = self.notes.lock().unwrap();
+        notes.insert(note.puuid.clone(), note);
+        self.persist(&notes)
+    }
+
+    /// 删除玩家备注，返回是否存在该玩家
+    pub fn remove(&self, puuid: &str) -> Result<bool> {
+        let mut notes = self.notes.lock().unwrap();
+        if notes.remove(puuid).is_none() {
+            return Ok(false);
+        }
+        self.persist(&notes)?;
+        Ok(true)
+    }
+
+    // 与导出的文件格式相同
+    fn persist(&self, notes: &BTreeMap<String, PlayerNote>) -> Result<()> {
+        let Some(path) = &self.path else {
+            return Ok(());
+        };
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        let notes = notes.values().collect::<Vec<_>>();
+        std::fs::write(path, serde_json::to_string_pretty(&notes)?)?;
+        Ok(())
+    }
+}
+
+/// 从 JSON 文件导入备注并与已有备注合并，返回导入的数量
+pub fn import_notes(path: &Path) -> Result<usize> {
+    let notes = serde_json::from_str::<Vec<PlayerNote>>(&std::fs::read_to_string(path)?)?;
+    let mut count = 0;
+    for note in notes {
+        if note.puuid.is_empty() {
+            continue;
+        }
+        let merged = match NOTES.get(&note.puuid) {
+            Some(mut existing) => {
+                existing.merge(note);
+                existing
+            }
+            None => note,
+        };
+        NOTES.save(merged)?;
+        count += 1;
+    }
+    Ok(count)
+}
+
+/// 将全部备注导出为 JSON 文件，返回导出的数量
+pub fn export_notes(path: &Path) -> Result<usize> {
+    let notes = NOTES.all();
+    std::fs::write(path, serde_json::to_string_pretty(&notes)?)?;
+    Ok(notes.len())
+}
+
+/// 提醒有备注的玩家，同一局中每位玩家只提醒一次
+pub(crate) fn alert_players<'a>(puuids: impl IntoIterator<Item = &'a str>, stage: &str) {
+    let me = CONTEXT.me.read().unwrap().puuid.clone();
+    let puuids = {
+        let mut alerted = CONTEXT.alerted_players.lock().unwrap();
+        puuids
+            .into_iter()
+            .filter(|puuid| !puuid.is_empty() && *puuid != me)
+            .filter(|puuid| alerted.insert(puuid.to_string()))
+            .collect::<HashSet<_>>()
+    };
+    for puuid in puuids {
+        let Some(note) = NOTES.get(puuid) else {
+            continue;
+        };
+        let alert = format!("{stage}：{note}");
+        if note.blocked {
+            warn!("{alert}");
+        } else {
+            info!("{alert}");
+        }
+        CONTEXT.player_alerts.write().unwrap().push(alert);
+    }
+}
+
+#[test]
+fn test_player_note() {
+    assert_eq!(
+        PlayerNote::parse_tags(" troll，good jungler,, troll "),
+        vec!["troll", "good jungler"]
+    );
+
+    let mut note = PlayerNote {
+        puuid: "a".to_string(),
+        name: "玩家A".to_string(),
+        tags: vec!["troll".to_string()],
+        ..Default::default()
+    };
+    assert_eq!(note.to_string(), "标记玩家 玩家A（troll）");
+    note.merge(PlayerNote {
+        puuid: "a".to_string(),
+        tags: vec!["troll".to_string(), "afk".to_string()],
+        note: "经常挂机".to_string(),
+        blocked: true,
+        ..Default::default()
+    });
+    assert_eq!(note.tags, vec!["troll", "afk"]);
+    assert_eq!(note.to_string(), "黑名单玩家 玩家A（troll、afk）：经常挂机");
+
+    // 导入的文件可以省略字段
+    let notes: Vec<PlayerNote> =
+        serde_json::from_str(r#"[{"puuid": "b", "tags": ["friend of friend"]}]"#)
+            .expect("Deserialization failed");
+    assert_eq!(notes[0].to_string(), "标记玩家 b（friend of friend）");
+}
+
+#[test]
+fn test_player_notes() {
+    let path = std::env::temp_dir().join(format!("player_notes_{}.json", std::process::id()));
+    let _ = std::fs::remove_file(&path);
+    let notes = PlayerNotes::open(path.clone());
+    let note = PlayerNote {
+        puuid: "b".to_string(),
+        name: "玩家B".to_string(),
+        tags: vec!["troll".to_string()],
+        blocked: true,
+        ..Default::default()
+    };
+    notes.save(note.clone()).unwrap();
+    assert_eq!(notes.get("b"), Some(note.clone()));
+
+    // 重新打开后读取保存的备注
+    let notes = PlayerNotes::open(path.clone());
+    assert_eq!(notes.all(), vec![note]);
+    assert!(notes.remove("b").unwrap());
+    assert!(!notes.remove("b").unwrap());
+    assert!(PlayerNotes::open(path.clone()).all().is_empty());
+
+    // 无法解析的文件备份后为空
+    std::fs::write(&path, "not json").unwrap();
+    assert!(PlayerNotes::open(path.clone()).all().is_empty());
+    assert!(path.with_extension("json.bak").exists());
+    let _ = std::fs::remove_file(&path);
+    let _ = std::fs::remove_file(path.with_extension("json.bak"));
+}
@@ -0,0 +1,202 @@
+//! 分析结果的输出位置，每项功能可以同时输出到多个位置
+
+use std::{fs::OpenOptions, io::Write, path::PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{Result, library::data_dir};
+
+// 分析报告文件所在的目录
+pub const REPORTS: &str = "reports";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Sink {
+    // 英雄选择聊天框
+    Chat,
+    // 图形界面的分析窗口
+    Panel,
+    // 日志，命令行中输出到控制台
+    Console,
+    // 追加到数据目录中的报告文件
+    File,
+}
+
+impl Sink {
+    pub const ALL: [Sink; 4] = [Sink::Chat, Sink::Panel, Sink::Console, Sink::File];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Sink::Chat => "聊天框",
+            Sink::Panel => "界面",
+            Sink::Console => "日志",
+            Sink::File => "文件",
+        }
+    }
+}
+
+/// 产生分析结果的功能
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Feature {
+    // 英雄选择中的队友分析
+    TeamAnalysis,
+    // 游戏开始时的全部玩家分析
+    LobbyReport,
+    // 游戏结束后自己的数据总结
+    PostGameReport,
+}
+
+impl Feature {
+    pub const ALL: [Feature; 3] = [
+        Feature::TeamAnalysis,
+        Feature::LobbyReport,
+        Feature::PostGameReport,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Feature::TeamAnalysis => "队友分析",
+            Feature::LobbyReport => "对局报告",
+            Feature::PostGameReport => "赛后报告",
+        }
+    }
+
+    /// 只有队友分析可以发送到聊天框，对局报告包含敌方数据，赛后报告只供自己查看
+    pub fn supports(self, sink: Sink) -> bool {
+        self == Feature::TeamAnalysis || sink != Sink::Chat
+    }
+
+    fn file_name(self) -> &'static str {
+        match self {
+            Feature::TeamAnalysis => "team_analysis.txt",
+            Feature::LobbyReport => "lobby_report.txt",
+            Feature::PostGameReport => "post_game_report.txt",
+        }
+    }
+}
+
+/// 一项功能启用的输出位置
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Sinks {
+    pub chat: bool,
+    pub panel: bool,
+    pub console: bool,
+    pub file: bool,
+}
+
+impl Sinks {
+    /// 没有任何输出位置时不需要进行分析
+    pub fn any(self) -> bool {
+        Sink::ALL.into_iter().any(|sink| self.contains(sink))
+    }
+
+    pub fn contains(self, sink: Sink) -> bool {
+        match sink {
+            Sink::Chat => self.chat,
+            Sink::Panel => self.panel,
+            Sink::Console => self.console,
+            Sink::File => self.file,
+        }
+    }
+
+    pub fn get_mut(&mut self, sink: Sink) -> &mut bool {
+        match sink {
+            Sink::Chat => &mut self.chat,
+            Sink::Panel => &mut self.panel,
+            Sink::Console => &mut self.console,
+            Sink::File => &mut self.file,
+        }
+    }
+}
+
+/// 各功能的输出位置
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct OutputSettings {
+    pub team_analysis: Sinks,
+    pub lobby_report: Sinks,
+    pub post_game_report: Sinks,
+}
+
+impl Default for OutputSettings {
+    fn default() -> Self {
+        Self {
+            team_analysis: Sinks {
+                chat: true,
+                panel: true,
+                ..Default::default()
+            },
+            lobby_report: Sinks {
+                panel: true,
+                console: true,
+                ..Default::default()
+            },
+            post_game_report: Sinks {
+                panel: true,
+                console: true,
+                ..Default::default()
+            },
+        }
+    }
+}
+
+impl OutputSettings {
+    /// 功能的输出位置，去掉该功能不支持的位置
+    pub fn sinks(&self, feature: Feature) -> Sinks {
+        let mut sinks = match feature {
+            Feature::TeamAnalysis => self.team_analysis,
+            Feature::LobbyReport => self.lobby_report,
+            Feature::PostGameReport => self.post_game_report,
+        };
+        for sink in Sink::ALL {
+            if !feature.supports(sink) {
+                *sinks.get_mut(sink) = false;
+            }
+        }
+        sinks
+    }
+
+    pub fn sinks_mut(&mut self, feature: Feature) -> &mut Sinks {
+        match feature {
+            Feature::TeamAnalysis => &mut self.team_analysis,
+            Feature::LobbyReport => &mut self.lobby_report,
+            Feature::PostGameReport => &mut self.post_game_report,
+        }
+    }
+}
+
+/// 将分析结果追加到功能对应的报告文件，每条记录前添加时间
+pub fn append_report(feature: Feature, text: &str) -> Result<PathBuf> {
+    let dir = data_dir().join(REPORTS);
+    std::fs::create_dir_all(&dir)?;
+    let path = dir.join(feature.file_name());
+    let mut file = OpenOptions::new().create(true).append(true).open(&path)?;
+    writeln!(
+        file,
+        "==== {} ====\n{}\n",
+        chrono::Local::now().format("%Y-%m-%d %H:%M:%S"),
+        text.trim_end()
+    )?;
+    Ok(path)
+}
+
+#[test]
+fn test_output_sinks() {
+    let mut settings = OutputSettings::default();
+    assert!(settings.sinks(Feature::TeamAnalysis).contains(Sink::Chat));
+
+    // 对局报告不会发送到聊天框
+    *settings.sinks_mut(Feature::LobbyReport) = Sinks {
+        chat: true,
+        ..Default::default()
+    };
+    assert!(!settings.sinks(Feature::LobbyReport).any());
+
+    *settings
+        .sinks_mut(Feature::TeamAnalysis)
+        .get_mut(Sink::Chat) = false;
+    *settings
+        .sinks_mut(Feature::TeamAnalysis)
+        .get_mut(Sink::Panel) = false;
+    assert!(!settings.sinks(Feature::TeamAnalysis).any());
+}
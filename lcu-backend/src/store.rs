@@ -0,0 +1,480 @@
+//! 本地对局数据库，保存查询过的对局，再次分析同一玩家时只查询新的对局
+
+use std::{
+    ops::RangeInclusive,
+    path::Path,
+    sync::{LazyLock, Mutex},
+};
+
+use log::{debug, error};
+use rusqlite::{Connection, OptionalExtension, params};
+
+use crate::{Result, api::match_history::REMAKE_DURATION, library::data_dir};
+
+// 数据库文件名，位于数据目录中
+pub const DATABASE: &str = "matches.db";
+// 对局默认保留的天数
+pub const DEFAULT_RETENTION_DAYS: u16 = 90;
+const DAY_MILLIS: i64 = 24 * 60 * 60 * 1000;
+
+pub static STORE: LazyLock<MatchStore> = LazyLock::new(|| {
+    let dir = data_dir();
+    std::fs::create_dir_all(&dir)
+        .map_err(Into::into)
+        .and_then(|_| MatchStore::open(&dir.join(DATABASE)))
+        .unwrap_or_else(|e| {
+            error!("打开对局数据库失败，本次运行使用内存数据库: {e}");
+            MatchStore::open_in_memory().expect("Failed to open in-memory database")
+        })
+});
+
+// 按顺序执行的数据库迁移，已执行的数量记录在 user_version 中，只能在末尾添加
+const MIGRATIONS: &[&str] = &[
+    // 战绩列表中的对局只包含查询的玩家，完整对局详情包含全部玩家
+    "CREATE TABLE matches (
+        puuid TEXT NOT NULL,
+        game_id INTEGER NOT NULL,
+        game_creation INTEGER NOT NULL,
+        queue_id INTEGER NOT NULL,
+        game_mode TEXT NOT NULL,
+        duration INTEGER NOT NULL,
+        win INTEGER NOT NULL,
+        kills INTEGER NOT NULL,
+        deaths INTEGER NOT NULL,
+        assists INTEGER NOT NULL,
+        damage INTEGER NOT NULL,
+        gold INTEGER NOT NULL,
+        cs INTEGER NOT NULL,
+        vision INTEGER NOT NULL,
+        data TEXT NOT NULL,
+        PRIMARY KEY (puuid, game_id)
+    );
+    CREATE INDEX matches_creation ON matches (puuid, game_creation DESC);
+    CREATE TABLE games (
+        game_id INTEGER PRIMARY KEY,
+        game_creation INTEGER NOT NULL,
+        data TEXT NOT NULL
+    );
+    CREATE TABLE player_rollups (
+        puuid TEXT NOT NULL,
+        queue_id INTEGER NOT NULL,
+        games INTEGER NOT NULL,
+        wins INTEGER NOT NULL,
+        kills INTEGER NOT NULL,
+        deaths INTEGER NOT NULL,
+        assists INTEGER NOT NULL,
+        damage INTEGER NOT NULL,
+        gold INTEGER NOT NULL,
+        cs INTEGER NOT NULL,
+        vision INTEGER NOT NULL,
+        duration INTEGER NOT NULL,
+        PRIMARY KEY (puuid, queue_id)
+    );",
+    // 赛后报告不受保留天数限制
+    "CREATE TABLE post_game_reports (
+        game_id INTEGER PRIMARY KEY,
+        created_at INTEGER NOT NULL,
+        data TEXT NOT NULL
+    );",
+    // 汇总改为从 matches 按需统计，match_ranges 记录从战绩列表连续保存的对局开始时间范围
+    "DROP TABLE player_rollups;
+    CREATE TABLE match_ranges (
+        puuid TEXT PRIMARY KEY,
+        newest INTEGER NOT NULL,
+        oldest INTEGER NOT NULL
+    );",
+];
+
+/// 战绩列表中的一局，统计字段单独保存用于汇总
+#[derive(Debug, Default, Clone)]
+pub(crate) struct MatchRecord {
+    pub game_id: u64,
+    pub game_creation: i64,
+    pub queue_id: i32,
+    pub game_mode: String,
+    pub duration: u32,
+    pub win: bool,
+    pub kills: u32,
+    pub deaths: u32,
+    pub assists: u32,
+    pub damage: u32,
+    pub gold: u32,
+    pub cs: u32,
+    pub vision: u32,
+    pub data: String, // 原始 JSON
+}
+
+/// 玩家在一个队列中最近已保存对局的汇总，由 recent_rollup 从 matches 统计，不包含重开的对局
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct PlayerRollup {
+    pub games: u32,
+    pub wins: u32,
+    pub kills: u32,
+    pub deaths: u32,
+    pub assists: u32,
+    pub damage: u64,
+    pub gold: u64,
+    pub cs: u32,
+    pub vision: u32,
+    pub duration: u64, // 秒
+}
+
+pub struct MatchStore {
+    conn: Mutex<Connection>,
+}
+
+impl MatchStore {
+    pub fn open(path: &Path) -> Result<Self> {
+        Self::with_connection(Connection::open(path)?)
+    }
+
+    pub fn open_in_memory() -> Result<Self> {
+        Self::with_connection(Connection::open_in_memory()?)
+    }
+
+    fn with_connection(mut conn: Connection) -> Result<Self> {
+        migrate(&mut conn)?;
+        Ok(Self {
+            conn: Mutex::new(conn),
+        })
+    }
+
+    /// 玩家连续保存的对局开始时间范围 (最新, 最早)，范围内的对局与战绩列表一致，没有时为None
+    pub(crate) fn synced_range(&self, puuid: &str) -> Result<Option<(i64, i64)>> {
+        let conn = self.conn.lock().unwrap();
+        Ok(conn
+            .query_row(
+                "SELECT newest, oldest FROM match_ranges WHERE puuid = ?1",
+                [puuid],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .optional()?)
+    }
+
+    /// 替换玩家连续保存的对局范围，范围内的对局需要已经保存
+    pub(crate) fn set_synced_range(&self, puuid: &str, newest: i64, oldest: i64) -> Result<()> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "INSERT OR REPLACE INTO match_ranges VALUES (?1, ?2, ?3)",
+            params![puuid, newest, oldest],
+        )?;
+        Ok(())
+    }
+
+    /// 保存玩家的对局，已保存的对局会被覆盖
+    pub(crate) fn insert_matches(&self, puuid: &str, records: &[MatchRecord]) -> Result<()> {
+        if records.is_empty() {
+            return Ok(());
+        }
+        let mut conn = self.conn.lock().unwrap();
+        let tx = conn.transaction()?;
+        {
+            let mut stmt = tx.prepare_cached(
+                "INSERT OR REPLACE INTO matches VALUES
+                (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15)",
+            )?;
+            for r in records {
+                stmt.execute(params![
+                    puuid,
+                    r.game_id as i64,
+                    r.game_creation,
+                    r.queue_id,
+                    r.game_mode,
+                    r.duration,
+                    r.win,
+                    r.kills,
+                    r.deaths,
+                    r.assists,
+                    r.damage,
+                    r.gold,
+                    r.cs,
+                    r.vision,
+                    r.data,
+                ])?;
+            }
+        }
+        tx.commit()?;
+        Ok(())
+    }
+
+    /// 开始时间在 creation 范围内的对局原始数据，按时间从新到旧
+    pub(crate) fn load_matches(
+        &self,
+        puuid: &str,
+        creation: RangeInclusive<i64>,
+        limit: usize,
+    ) -> Result<Vec<String>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare_cached(
+            "SELECT data FROM matches
+            WHERE puuid = ?1 AND game_creation >= ?2 AND game_creation <= ?3
+            ORDER BY game_creation DESC LIMIT ?4",
+        )?;
+        let rows = stmt.query_map(
+            params![puuid, creation.start(), creation.end(), limit as i64],
+            |row| row.get(0),
+        )?;
+        Ok(rows.collect::<rusqlite::Result<Vec<String>>>()?)
+    }
+
+    pub(crate) fn load_game(&self, game_id: u64) -> Result<Option<String>> {
+        let conn = self.conn.lock().unwrap();
+        Ok(conn
+            .query_row(
+                "SELECT data FROM games WHERE game_id = ?1",
+                [game_id as i64],
+                |row| row.get(0),
+            )
+            .optional()?)
+    }
+
+    pub(crate) fn insert_game(&self, game_id: u64, game_creation: i64, data: &str) -> Result<()> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "INSERT OR REPLACE INTO games VALUES (?1, ?2, ?3)",
+            params![game_id as i64, game_creation, data],
+        )?;
+        Ok(())
+    }
+
+    /// 玩家在队列中最近 limit 场对局的汇总，不包含重开和指定的对局
+    pub(crate) fn recent_rollup(
+        &self,
+        puuid: &str,
+        queue_id: i32,
+        exclude_game: u64,
+        limit: usize,
+    ) -> Result<PlayerRollup> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare_cached(
+            "SELECT COUNT(*), COALESCE(SUM(win), 0), COALESCE(SUM(kills), 0),
+                COALESCE(SUM(deaths), 0), COALESCE(SUM(assists), 0), COALESCE(SUM(damage), 0),
+                COALESCE(SUM(gold), 0), COALESCE(SUM(cs), 0), COALESCE(SUM(vision), 0),
+                COALESCE(SUM(duration), 0)
+            FROM (SELECT * FROM matches
+                WHERE puuid = ?1 AND queue_id = ?2 AND game_id != ?3 AND duration >= ?4
+                ORDER BY game_creation DESC LIMIT ?5)",
+        )?;
+        Ok(stmt.query_row(
+            params![
+                puuid,
+                queue_id,
+                exclude_game as i64,
+                REMAKE_DURATION,
+                limit as i64
+            ],
+            |row| {
+                Ok(PlayerRollup {
+                    games: row.get(0)?,
+                    wins: row.get(1)?,
+                    kills: row.get(2)?,
+                    deaths: row.get(3)?,
+                    assists: row.get(4)?,
+                    damage: row.get(5)?,
+                    gold: row.get(6)?,
+                    cs: row.get(7)?,
+                    vision: row.get(8)?,
+                    duration: row.get(9)?,
+                })
+            },
+        )?)
+    }
+
+    /// 玩家在队列中最近 limit 场对局的胜负，按时间从新到旧，不包含重开和指定的对局
+    pub(crate) fn recent_results(
+        &self,
+        puuid: &str,
+        queue_id: i32,
+        exclude_game: u64,
+        limit: usize,
+    ) -> Result<Vec<bool>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare_cached(
+            "SELECT win FROM matches
+            WHERE puuid = ?1 AND queue_id = ?2 AND game_id != ?3 AND duration >= ?4
+            ORDER BY game_creation DESC LIMIT ?5",
+        )?;
+        let rows = stmt.query_map(
+            params![
+                puuid,
+                queue_id,
+                exclude_game as i64,
+                REMAKE_DURATION,
+                limit as i64
+            ],
+            |row| row.get(0),
+        )?;
+        Ok(rows.collect::<rusqlite::Result<Vec<bool>>>()?)
+    }
+
+    pub(crate) fn insert_report(&self, game_id: u64, created_at: i64, data: &str) -> Result<()> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "INSERT OR REPLACE INTO post_game_reports VALUES (?1, ?2, ?3)",
+            params![game_id as i64, created_at, data],
+        )?;
+        Ok(())
+    }
+
+    /// 最近的赛后报告原始数据，按时间从新到旧
+    pub(crate) fn load_reports(&self, limit: usize) -> Result<Vec<String>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare_cached(
+            "SELECT data FROM post_game_reports ORDER BY created_at DESC LIMIT ?1",
+        )?;
+        let rows = stmt.query_map([limit as i64], |row| row.get(0))?;
+        Ok(rows.collect::<rusqlite::Result<Vec<String>>>()?)
+    }
+
+    /// 删除开始时间早于 now - days 天的对局，days 为0时保留全部对局，返回删除的对局数
+    pub fn purge(&self, days: u16, now: i64) -> Result<usize> {
+        if days == 0 {
+            return Ok(0);
+        }
+        let before = now - days as i64 * DAY_MILLIS;
+        let mut conn = self.conn.lock().unwrap();
+        let tx = conn.transaction()?;
+        let removed = tx.execute("DELETE FROM matches WHERE game_creation < ?1", [before])?;
+        tx.execute("DELETE FROM games WHERE game_creation < ?1", [before])?;
+        tx.execute("DELETE FROM match_ranges WHERE newest < ?1", [before])?;
+        tx.execute(
+            "UPDATE match_ranges SET oldest = ?1 WHERE oldest < ?1",
+            [before],
+        )?;
+        tx.commit()?;
+        Ok(removed)
+    }
+}
+
+/// 执行尚未执行的迁移
+fn migrate(conn: &mut Connection) -> Result<()> {
+    let version: usize = conn.query_row("PRAGMA user_version", [], |row| row.get(0))?;
+    if version >= MIGRATIONS.len() {
+        return Ok(());
+    }
+    let tx = conn.transaction()?;
+    for (index, migration) in MIGRATIONS.iter().enumerate().skip(version) {
+        debug!("执行对局数据库迁移 {}", index + 1);
+        tx.execute_batch(migration)?;
+    }
+    tx.pragma_update(None, "user_version", MIGRATIONS.len())?;
+    tx.commit()?;
+    Ok(())
+}
+
+#[test]
+fn test_match_store() {
+    let store = MatchStore::open_in_memory().unwrap();
+    let record = |game_id: u64, game_creation: i64, duration: u32, win: bool| MatchRecord {
+        game_id,
+        game_creation,
+        queue_id: 420,
+        duration,
+        win,
+        kills: 5,
+        deaths: 2,
+        data: format!(r#"{{"gameId": {game_id}}}"#),
+        ..Default::default()
+    };
+    assert_eq!(store.synced_range("a").unwrap(), None);
+    store
+        .insert_matches(
+            "a",
+            &[
+                record(3, 3 * DAY_MILLIS, 1800, true),
+                record(2, 2 * DAY_MILLIS, 1800, false),
+                // 重开的对局保存但不计入汇总
+                record(1, DAY_MILLIS, 200, false),
+            ],
+        )
+        .unwrap();
+    // 重复保存同一局不会重复统计
+    store
+        .insert_matches("a", &[record(3, 3 * DAY_MILLIS, 1800, true)])
+        .unwrap();
+
+    store
+        .set_synced_range("a", 3 * DAY_MILLIS, DAY_MILLIS)
+        .unwrap();
+    assert_eq!(
+        store.synced_range("a").unwrap(),
+        Some((3 * DAY_MILLIS, DAY_MILLIS))
+    );
+    assert_eq!(
+        store.load_matches("a", 0..=2 * DAY_MILLIS, 10).unwrap(),
+        vec![r#"{"gameId": 2}"#, r#"{"gameId": 1}"#]
+    );
+    assert_eq!(
+        store
+            .load_matches("a", 2 * DAY_MILLIS..=3 * DAY_MILLIS, 10)
+            .unwrap(),
+        vec![r#"{"gameId": 3}"#, r#"{"gameId": 2}"#]
+    );
+    assert_eq!(store.load_matches("a", 0..=i64::MAX, 1).unwrap().len(), 1);
+    let rollup = store.recent_rollup("a", 420, 0, 10).unwrap();
+    assert_eq!((rollup.games, rollup.wins), (2, 1));
+    assert_eq!(rollup.kills, 10);
+    assert_eq!(rollup.duration, 3600);
+    let recent = store.recent_rollup("a", 420, 3, 10).unwrap();
+    assert_eq!((recent.games, recent.wins), (1, 0));
+    assert_eq!(store.recent_rollup("a", 440, 0, 10).unwrap().games, 0);
+    assert_eq!(
+        store.recent_results("a", 420, 0, 10).unwrap(),
+        vec![true, false]
+    );
+
+    store.insert_report(3, 1, "old").unwrap();
+    store.insert_report(4, 2, "new").unwrap();
+    assert_eq!(store.load_reports(10).unwrap(), vec!["new", "old"]);
+
+    store.insert_game(3, 3 * DAY_MILLIS, "{}").unwrap();
+    assert_eq!(store.load_game(3).unwrap().as_deref(), Some("{}"));
+    assert_eq!(store.load_game(4).unwrap(), None);
+
+    // 保留天数为0时不删除
+    assert_eq!(store.purge(0, 10 * DAY_MILLIS).unwrap(), 0);
+    assert_eq!(store.purge(8, 10 * DAY_MILLIS).unwrap(), 1);
+    // 连续范围的最早时间随删除的对局推后
+    assert_eq!(
+        store.synced_range("a").unwrap(),
+        Some((3 * DAY_MILLIS, 2 * DAY_MILLIS))
+    );
+    assert_eq!(store.recent_rollup("a", 420, 0, 10).unwrap().games, 2);
+    assert_eq!(store.purge(7, 10 * DAY_MILLIS).unwrap(), 1);
+    assert_eq!(store.recent_rollup("a", 420, 0, 10).unwrap().games, 1);
+    assert!(store.load_game(3).unwrap().is_some());
+    assert_eq!(store.purge(6, 10 * DAY_MILLIS).unwrap(), 1);
+    assert_eq!(store.recent_rollup("a", 420, 0, 10).unwrap().games, 0);
+    assert_eq!(store.load_game(3).unwrap(), None);
+    assert_eq!(store.synced_range("a").unwrap(), None);
+
+    // 迁移只执行一次
+    let mut conn = store.conn.into_inner().unwrap();
+    migrate(&mut conn).unwrap();
+    let version: usize = conn
+        .query_row("PRAGMA user_version", [], |row| row.get(0))
+        .unwrap();
+    assert_eq!(version, MIGRATIONS.len());
+}
+
+#[test]
+fn test_migrate_existing_database() {
+    // 只执行过前两个迁移的数据库
+    let conn = Connection::open_in_memory().unwrap();
+    conn.execute_batch(&MIGRATIONS[..2].join("\n")).unwrap();
+    conn.pragma_update(None, "user_version", 2).unwrap();
+    let store = MatchStore::with_connection(conn).unwrap();
+    store.set_synced_range("a", 2, 1).unwrap();
+    assert_eq!(store.synced_range("a").unwrap(), Some((2, 1)));
+    assert_eq!(store.purge(1, DAY_MILLIS).unwrap(), 0);
+
+    let conn = store.conn.into_inner().unwrap();
+    let rollups: usize = conn
+        .query_row(
+            "SELECT COUNT(*) FROM sqlite_master WHERE name = 'player_rollups'",
+            [],
+            |row| row.get(0),
+        )
+        .unwrap();
+    assert_eq!(rollups, 0);
+}
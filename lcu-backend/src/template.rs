@@ -0,0 +1,284 @@
+//! 战绩分析消息模板
+//!
+//! `{name}` 替换为对应数据，`{#name}...{/name}` 在数据非零/非空时显示，
+//! `{^name}...{/name}` 在数据为零/空时显示，`{{` 和 `}}` 表示花括号本身。
+
+use std::collections::BTreeMap;
+use std::fmt::Display;
+
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+#[derive(Error, Debug, PartialEq, Eq)]
+pub enum TemplateError {
+    #[error("未知的占位符: {0}")]
+    UnknownPlaceholder(String),
+    #[error("条件块未结束: {0}")]
+    UnclosedSection(String),
+    #[error("多余的结束标记: {0}")]
+    UnexpectedClose(String),
+    #[error("花括号未闭合")]
+    UnclosedBrace,
+}
+
+/// 模板中可以使用的数据
+#[derive(Debug, Clone, PartialEq)]
+pub enum TemplateValue {
+    Text(String),
+    Int(i64),
+    Float(f32),
+    Bool(bool),
+}
+
+impl TemplateValue {
+    fn is_truthy(&self) -> bool {
+        match self {
+            TemplateValue::Text(text) => !text.is_empty(),
+            TemplateValue::Int(value) => *value != 0,
+            TemplateValue::Float(value) => *value != 0.0,
+            TemplateValue::Bool(value) => *value,
+        }
+    }
+}
+
+impl Display for TemplateValue {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            TemplateValue::Text(text) => write!(f, "{text}"),
+            TemplateValue::Int(value) => write!(f, "{value}"),
+            TemplateValue::Float(value) => write!(f, "{value:.2}"),
+            TemplateValue::Bool(value) => write!(f, "{value}"),
+        }
+    }
+}
+
+/// 可以填充模板的数据来源
+pub trait TemplateData {
+    /// 全部占位符及说明
+    const PLACEHOLDERS: &'static [(&'static str, &'static str)];
+
+    fn value(&self, name: &str) -> Option<TemplateValue>;
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Node {
+    Text(String),
+    Value(String),
+    Section {
+        name: String,
+        inverted: bool,
+        children: Vec<Node>,
+    },
+}
+
+/// 解析后的模板
+#[derive(Debug, Clone)]
+pub struct Template {
+    nodes: Vec<Node>,
+}
+
+impl Template {
+    /// 解析模板，包含不在 placeholders 中的占位符时返回错误
+    pub fn parse(source: &str, placeholders: &[(&str, &str)]) -> Result<Self, TemplateError> {
+        let known = |name: &str| placeholders.iter().any(|(known, _)| *known == name);
+        // 未结束的条件块: (名称, 是否取反, 条件块之前的节点)
+        let mut stack: Vec<(String, bool, Vec<Node>)> = vec![];
+        let mut nodes = vec![];
+        let mut text = String::new();
+        let mut chars = source.chars().peekable();
+
+        while let Some(c) = chars.next() {
+            match c {
+                '{' if chars.peek() == Some(&'{') => {
+                    chars.next();
+                    text.push('{');
+                }
+                '}' if chars.peek() == Some(&'}') => {
+                    chars.next();
+                    text.push('}');
+                }
+                '{' => {
+                    let mut tag = String::new();
+                    loop {
+                        match chars.next() {
+                            Some('}') => break,
+                            Some(c) => tag.push(c),
+                            None => return Err(TemplateError::UnclosedBrace),
+                        }
+                    }
+                    if !text.is_empty() {
+                        nodes.push(Node::Text(std::mem::take(&mut text)));
+                    }
+                    let tag = tag.trim();
+                    let (prefix, name) = match tag.chars().next() {
+                        Some(prefix @ ('#' | '^' | '/')) => (Some(prefix), tag[1..].trim()),
+                        _ => (None, tag),
+                    };
+                    if !known(name) {
+                        return Err(TemplateError::UnknownPlaceholder(name.to_string()));
+                    }
+                    match prefix {
+                        Some('/') => {
+                            let Some((open, inverted, parent)) = stack.pop() else {
+                                return Err(TemplateError::UnexpectedClose(name.to_string()));
+                            };
+                            if open != name {
+                                return Err(TemplateError::UnclosedSection(open));
+                            }
+                            let children = std::mem::replace(&mut nodes, parent);
+                            nodes.push(Node::Section {
+                                name: open,
+                                inverted,
+                                children,
+                            });
+                        }
+                        Some(prefix) => {
+                            let parent = std::mem::take(&mut nodes);
+                            stack.push((name.to_string(), prefix == '^', parent));
+                        }
+                        None => nodes.push(Node::Value(name.to_string())),
+                    }
+                }
+                c => text.push(c),
+            }
+        }
+        if let Some((name, _, _)) = stack.pop() {
+            return Err(TemplateError::UnclosedSection(name));
+        }
+        if !text.is_empty() {
+            nodes.push(Node::Text(text));
+        }
+        Ok(Template { nodes })
+    }
+
+    pub fn render<T: TemplateData>(&self, data: &T) -> String {
+        let mut output = String::new();
+        render_nodes(&self.nodes, data, &mut output);
+        output
+    }
+}
+
+fn render_nodes<T: TemplateData>(nodes: &[Node], data: &T, output: &mut String) {
+    for node in nodes {
+        match node {
+            Node::Text(text) => output.push_str(text),
+            Node::Value(name) => {
+                if let Some(value) = data.value(name) {
+                    output.push_str(&value.to_string());
+                }
+            }
+            Node::Section {
+                name,
+                inverted,
+                children,
+            } => {
+                let truthy = data.value(name).is_some_and(|value| value.is_truthy());
+                if truthy != *inverted {
+                    render_nodes(children, data, output);
+                }
+            }
+        }
+    }
+}
+
+/// 各语言的战绩分析模板
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AnalysisTemplates {
+    // 发送消息使用的语言
+    pub language: String,
+    // 语言 -> 模板
+    pub templates: BTreeMap<String, String>,
+}
+
+impl Default for AnalysisTemplates {
+    fn default() -> Self {
+        let templates = [
+            (
+                "zh_CN",
+                "{name}{#premade}（组队{premade}）{/premade}战绩信息：{#rank}{rank}{/rank}\nkda={kda}，分均伤害={dpm}\n胜率={wins}/{total}，{streak}{#win_streak}连胜{/win_streak}{^win_streak}连败{/win_streak}",
+            ),
+            (
+                "en_US",
+                "{name}{#premade} (party {premade}){/premade} recent games:{#rank} {rank}{/rank}\nKDA={kda}, DPM={dpm}\nWins={wins}/{total}, {streak} {#win_streak}win{/win_streak}{^win_streak}loss{/win_streak} streak",
+            ),
+        ];
+        Self {
+            language: "zh_CN".to_string(),
+            templates: templates
+                .into_iter()
+                .map(|(language, template)| (language.to_string(), template.to_string()))
+                .collect(),
+        }
+    }
+}
+
+impl AnalysisTemplates {
+    /// 当前语言的模板，该语言未配置时使用第一个模板
+    pub fn current(&self) -> &str {
+        self.templates
+            .get(&self.language)
+            .or_else(|| self.templates.values().next())
+            .map_or("", String::as_str)
+    }
+
+    /// 校验通过后保存模板
+    pub fn set<T: TemplateData>(
+        &mut self,
+        language: &str,
+        source: &str,
+    ) -> Result<(), TemplateError> {
+        Template::parse(source, T::PLACEHOLDERS)?;
+        self.templates
+            .insert(language.to_string(), source.to_string());
+        Ok(())
+    }
+
+    /// 使用当前语言的模板渲染，模板无效时使用默认模板
+    pub fn render<T: TemplateData>(&self, data: &T) -> String {
+        let template = Template::parse(self.current(), T::PLACEHOLDERS).unwrap_or_else(|_| {
+            let default = AnalysisTemplates::default();
+            Template::parse(default.current(), T::PLACEHOLDERS).unwrap()
+        });
+        template.render(data)
+    }
+}
+
+#[cfg(test)]
+struct Sample;
+
+#[cfg(test)]
+impl TemplateData for Sample {
+    const PLACEHOLDERS: &'static [(&'static str, &'static str)] =
+        &[("name", "名称"), ("kda", "KDA"), ("streak", "连胜")];
+
+    fn value(&self, name: &str) -> Option<TemplateValue> {
+        match name {
+            "name" => Some(TemplateValue::Text("Faker".to_string())),
+            "kda" => Some(TemplateValue::Float(3.456)),
+            "streak" => Some(TemplateValue::Int(0)),
+            _ => None,
+        }
+    }
+}
+
+#[test]
+fn test_template_render() {
+    let render = |source: &str| {
+        Template::parse(source, Sample::PLACEHOLDERS).map(|template| template.render(&Sample))
+    };
+    assert_eq!(render("{name}: kda={kda}").unwrap(), "Faker: kda=3.46");
+    assert_eq!(
+        render("{#streak}{streak}连胜{/streak}{^streak}无连胜{/streak}").unwrap(),
+        "无连胜"
+    );
+    assert_eq!(render("{{name}}").unwrap(), "{name}");
+    assert_eq!(
+        render("{dpm}").unwrap_err(),
+        TemplateError::UnknownPlaceholder("dpm".to_string())
+    );
+    assert_eq!(
+        render("{#name}{kda}").unwrap_err(),
+        TemplateError::UnclosedSection("name".to_string())
+    );
+    assert_eq!(render("{name").unwrap_err(), TemplateError::UnclosedBrace);
+}
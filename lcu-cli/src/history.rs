@@ -0,0 +1,144 @@
+//! 导出战绩到文件，列结构见 MatchSummary::COLUMNS
+
+use std::{
+    fs::File,
+    io::{BufWriter, Write},
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use anyhow::Context;
+use lcu_backend::MatchSummary;
+use parquet::{
+    basic::Compression,
+    data_type::{BoolType, ByteArray, ByteArrayType, Int32Type, Int64Type},
+    file::{properties::WriterProperties, writer::SerializedFileWriter},
+    schema::parser::parse_message_type,
+};
+
+// 与 MatchSummary 的字段顺序一致，无符号整数按相同位宽的有符号整数保存
+const PARQUET_SCHEMA: &str = "
+message match_summary {
+    REQUIRED INT64 game_id;
+    REQUIRED INT64 game_creation (TIMESTAMP(MILLIS, true));
+    REQUIRED INT32 queue_id;
+    REQUIRED BYTE_ARRAY game_mode (UTF8);
+    REQUIRED INT32 duration;
+    REQUIRED BYTE_ARRAY puuid (UTF8);
+    REQUIRED INT32 champion_id;
+    REQUIRED BYTE_ARRAY position (UTF8);
+    REQUIRED BOOLEAN win;
+    REQUIRED INT32 kills;
+    REQUIRED INT32 deaths;
+    REQUIRED INT32 assists;
+    REQUIRED INT32 damage;
+    REQUIRED INT32 gold;
+    REQUIRED INT32 cs;
+    REQUIRED INT32 vision;
+}
+";
+
+#[derive(clap::ValueEnum, Clone, Copy, Debug)]
+pub enum Format {
+    Csv,
+    Json,
+    Parquet,
+}
+
+impl Format {
+    pub fn extension(self) -> &'static str {
+        match self {
+            Format::Csv => "csv",
+            Format::Json => "json",
+            Format::Parquet => "parquet",
+        }
+    }
+}
+
+/// 按格式写入对局摘要，每场对局一行
+pub fn write_summaries(
+    path: &Path,
+    format: Format,
+    summaries: &[MatchSummary],
+) -> anyhow::Result<()> {
+    match format {
+        Format::Csv => {
+            let mut writer = csv::Writer::from_path(path)?;
+            if summaries.is_empty() {
+                writer.write_record(MatchSummary::COLUMNS.iter().map(|(name, _)| name))?;
+            }
+            for summary in summaries {
+                writer.serialize(summary)?;
+            }
+            writer.flush()?;
+        }
+        Format::Json => {
+            let writer = BufWriter::new(File::create(path)?);
+            serde_json::to_writer_pretty(writer, summaries)?;
+        }
+        Format::Parquet => write_parquet(path, summaries)?,
+    }
+    Ok(())
+}
+
+// 按 PARQUET_SCHEMA 的顺序依次写入每一列
+macro_rules! write_columns {
+    ($row_group:expr, $summaries:expr, $($kind:ty => $value:expr),* $(,)?) => {
+        $(
+            let values = $summaries.iter().map($value).collect::<Vec<_>>();
+            let mut column = $row_group.next_column()?.context("列数与结构不一致")?;
+            column.typed::<$kind>().write_batch(&values, None, None)?;
+            column.close()?;
+        )*
+    };
+}
+
+fn write_parquet(path: &Path, summaries: &[MatchSummary]) -> anyhow::Result<()> {
+    let schema = Arc::new(parse_message_type(PARQUET_SCHEMA)?);
+    let properties = Arc::new(
+        WriterProperties::builder()
+            .set_compression(Compression::SNAPPY)
+            .build(),
+    );
+    let mut writer = SerializedFileWriter::new(File::create(path)?, schema, properties)?;
+    let mut row_group = writer.next_row_group()?;
+    write_columns!(row_group, summaries,
+        Int64Type => |s| s.game_id as i64,
+        Int64Type => |s| s.game_creation,
+        Int32Type => |s| s.queue_id,
+        ByteArrayType => |s| ByteArray::from(s.game_mode.as_str()),
+        Int32Type => |s| s.duration as i32,
+        ByteArrayType => |s| ByteArray::from(s.puuid.as_str()),
+        Int32Type => |s| s.champion_id as i32,
+        ByteArrayType => |s| ByteArray::from(s.position.as_str()),
+        BoolType => |s| s.win,
+        Int32Type => |s| s.kills as i32,
+        Int32Type => |s| s.deaths as i32,
+        Int32Type => |s| s.assists as i32,
+        Int32Type => |s| s.damage as i32,
+        Int32Type => |s| s.gold as i32,
+        Int32Type => |s| s.cs as i32,
+        Int32Type => |s| s.vision as i32,
+    );
+    row_group.close()?;
+    writer.close()?;
+    Ok(())
+}
+
+/// 对局详情的文件路径，与摘要文件同目录，如 history.csv 对应 history_games.jsonl
+pub fn details_path(path: &Path) -> PathBuf {
+    let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+    path.with_file_name(format!("{stem}_games.jsonl"))
+}
+
+/// 写入对局详情的原始 JSON，每行一场对局
+pub fn write_details(path: &Path, games: &[String]) -> anyhow::Result<()> {
+    let mut writer = BufWriter::new(File::create(path)?);
+    for game in games {
+        // 去掉原始数据中的换行，保证每行一场
+        let value = serde_json::from_str::<serde_json::Value>(game)?;
+        writeln!(writer, "{value}")?;
+    }
+    writer.flush()?;
+    Ok(())
+}
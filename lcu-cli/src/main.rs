@@ -1,7 +1,18 @@
-use anyhow::Ok;
-use lcu_backend::CONTEXT;
+mod history;
+
+use history::Format;
+use lcu_backend::{
+    CONTEXT, Champion, DEFAULT_ANALYSIS_GAMES, ItemSet, LcuClient, LockMode, PlayerScore, Position,
+    PostGameReport, RunePage, SkinStrategy, SpellPreset, SwapRule,
+    library::{self, ITEM_SETS, RUNES},
+    notes::{self, NOTES, PlayerNote},
+    output::{Sink, Sinks},
+    spell_id,
+    store::DEFAULT_RETENTION_DAYS,
+    template::{Template, TemplateData},
+};
 use std::{
-    collections::HashSet,
+    collections::{BTreeMap, HashSet},
     sync::{Arc, atomic::Ordering},
     time::Duration,
 };
@@ -41,6 +52,173 @@ impl LogLevel {
     }
 }
 
+#[derive(clap::ValueEnum, Clone, Debug)]
+enum Lock {
+    Immediately,
+    Delay,
+    Last,
+    Hover,
+}
+
+impl Lock {
+    fn to_mode(&self) -> LockMode {
+        match self {
+            Lock::Immediately => LockMode::Immediately,
+            Lock::Delay => LockMode::AfterDelay,
+            Lock::Last => LockMode::LastSeconds,
+            Lock::Hover => LockMode::HoverOnly,
+        }
+    }
+}
+
+#[derive(clap::ValueEnum, Clone, Debug)]
+enum Skin {
+    Default,
+    Favorite,
+    Random,
+    Chroma,
+}
+
+impl Skin {
+    fn to_strategy(&self) -> SkinStrategy {
+        match self {
+            Skin::Default => SkinStrategy::Default,
+            Skin::Favorite => SkinStrategy::Favorite,
+            Skin::Random => SkinStrategy::Random,
+            Skin::Chroma => SkinStrategy::RandomChroma,
+        }
+    }
+}
+
+#[derive(clap::ValueEnum, Clone, Debug)]
+enum Swap {
+    Ignore,
+    Accept,
+    Decline,
+    Smart,
+}
+
+impl Swap {
+    fn to_rule(&self) -> SwapRule {
+        match self {
+            Swap::Ignore => SwapRule::Ignore,
+            Swap::Accept => SwapRule::Accept,
+            Swap::Decline => SwapRule::Decline,
+            Swap::Smart => SwapRule::Smart,
+        }
+    }
+}
+
+#[derive(clap::ValueEnum, Clone, Debug)]
+enum Output {
+    Chat,
+    Console,
+    File,
+}
+
+impl Output {
+    fn to_sinks(outputs: &[Output]) -> Sinks {
+        let mut sinks = Sinks::default();
+        for output in outputs {
+            let sink = match output {
+                Output::Chat => Sink::Chat,
+                Output::Console => Sink::Console,
+                Output::File => Sink::File,
+            };
+            *sinks.get_mut(sink) = true;
+        }
+        sinks
+    }
+}
+
+#[derive(clap::Subcommand, Debug)]
+enum Command {
+    /// 管理本地符文库
+    Runes {
+        #[command(subcommand)]
+        action: LibraryAction,
+    },
+    /// 使用示例数据预览分析模板文件，未指定文件时预览默认模板
+    Template { file: Option<std::path::PathBuf> },
+    /// 管理本地出装库
+    ItemSets {
+        #[command(subcommand)]
+        action: LibraryAction,
+    },
+    /// 查看本地保存的赛后报告
+    PostGame {
+        /// 显示的报告数，从最近一局开始
+        #[arg(short = 'n', long, default_value_t = 1)]
+        limit: usize,
+        /// 同时导出为 Markdown 文件
+        #[arg(long)]
+        markdown: bool,
+    },
+    /// 管理玩家备注、标签和黑名单
+    Notes {
+        #[command(subcommand)]
+        action: NoteAction,
+    },
+    /// 导出战绩到文件，未指定玩家时导出自己的战绩
+    History {
+        /// 玩家的 puuid
+        #[arg(long, conflicts_with = "riot_id")]
+        puuid: Option<String>,
+        /// 玩家的 Riot ID（名称#标签）
+        #[arg(long)]
+        riot_id: Option<String>,
+        /// 只导出指定队列的对局，如 420 为单双排
+        #[arg(long)]
+        queue: Option<i32>,
+        /// 导出的对局数，从最近一局开始
+        #[arg(short = 'n', long, default_value_t = 20)]
+        count: usize,
+        #[arg(long, value_enum, default_value_t = Format::Csv)]
+        format: Format,
+        /// 输出文件，默认为当前目录下的 history.<格式>
+        #[arg(short, long)]
+        output: Option<std::path::PathBuf>,
+        /// 同时导出完整的对局详情到 <文件名>_games.jsonl
+        #[arg(long)]
+        details: bool,
+    },
+}
+
+#[derive(clap::Subcommand, Debug)]
+enum NoteAction {
+    /// 列出全部玩家标记
+    List,
+    /// 添加或更新玩家标记，使用 Riot ID（名称#标签）时需要客户端在运行
+    Add {
+        /// 玩家的 puuid 或 Riot ID
+        player: String,
+        /// 标签，多个标签用逗号分隔
+        #[arg(short, long, default_value = "")]
+        tags: String,
+        #[arg(short, long, default_value = "")]
+        note: String,
+        /// 加入黑名单
+        #[arg(long)]
+        block: bool,
+    },
+    /// 删除玩家标记
+    Remove { puuid: String },
+    /// 从 JSON 文件导入并与已有标记合并
+    Import { file: std::path::PathBuf },
+    /// 导出全部标记到 JSON 文件
+    Export { file: std::path::PathBuf },
+}
+
+#[derive(clap::Subcommand, Debug)]
+enum LibraryAction {
+    /// 列出本地库中的数据
+    List,
+    /// 将客户端中的数据导入本地库
+    Import,
+    /// 将本地库中指定名称的数据导出到客户端
+    Export { name: String },
+}
+
 #[derive(Parser, Debug)]
 #[command(name = "lcu")]
 #[command(version = env!("CARGO_PKG_VERSION"))]
@@ -48,18 +226,123 @@ impl LogLevel {
 struct Cli {
     #[arg(short = 'a', long, value_parser=clap::value_parser!(u8).range(0..=15), default_value_t = 3)]
     accept: u8,
+    /// 发送队友分析到聊天框，等同于在 --team-output 中添加 chat
     #[arg(short = 's', long)]
     send_analytics: bool,
+    /// 队友分析的输出位置，多个位置用逗号分隔
+    #[arg(long, value_enum, value_delimiter = ',', default_values_t = [Output::Console])]
+    team_output: Vec<Output>,
+    /// 对局报告的输出位置，多个位置用逗号分隔，不支持 chat
+    #[arg(long, value_enum, value_delimiter = ',', default_values_t = [Output::Console])]
+    report_output: Vec<Output>,
+    /// 赛后报告的输出位置，多个位置用逗号分隔，不支持 chat
+    #[arg(long, value_enum, value_delimiter = ',', default_values_t = [Output::Console])]
+    post_game_output: Vec<Output>,
+    /// 赛后报告同时导出为 Markdown 文件
+    #[arg(long)]
+    post_game_markdown: bool,
+    /// 将所有队友的分析合并为一条消息发送
+    #[arg(long)]
+    combine_analysis: bool,
+    /// 每位玩家分析的对局数，只统计同一队列且非重开的对局
+    #[arg(long, value_parser=clap::value_parser!(u8).range(5..=50), default_value_t = DEFAULT_ANALYSIS_GAMES)]
+    analysis_games: u8,
+    /// 本地对局数据库保留的天数，0表示永久保留
+    #[arg(long, default_value_t = DEFAULT_RETENTION_DAYS)]
+    retention_days: u16,
+    /// 分析消息使用的语言，如 zh_CN、en_US
+    #[arg(long, default_value = "zh_CN")]
+    template_lang: String,
+    /// 分析消息模板文件，保存为 --template-lang 指定的语言
+    #[arg(long)]
+    template: Option<std::path::PathBuf>,
+    /// 掉线后不自动重新连接
+    #[arg(long)]
+    no_reconnect: bool,
+    /// 英雄名称，可用 "位置:英雄" 为指定位置设置（top/jungle/mid/bot/support）
     #[arg(short = 'p', long)]
     pick: Vec<String>,
+    #[arg(long, value_enum, default_value_t = Lock::Immediately)]
+    lock: Lock,
+    #[arg(long, value_parser=clap::value_parser!(u8).range(0..=30), default_value_t = 0)]
+    lock_seconds: u8,
+    #[arg(long)]
+    intent: bool,
+    #[arg(long, default_value_t = 0)]
+    reroll_below: u8,
+    #[arg(long, value_parser=clap::value_parser!(u8).range(0..=10), default_value_t = 3)]
+    swap_cooldown: u8,
+    #[arg(short = 'b', long)]
+    ban: Vec<String>,
+    #[arg(short = 'w', long, value_parser=clap::value_parser!(u8).range(0..=30), default_value_t = 0)]
+    ban_wait: u8,
+    /// 召唤师技能，格式为 "[位置|模式|英雄:]技能1,技能2"
+    #[arg(long)]
+    spells: Vec<String>,
+    #[arg(long, value_enum)]
+    skin: Option<Skin>,
+    #[arg(long, value_enum, default_value_t = Swap::Ignore)]
+    trade: Swap,
+    #[arg(long, value_enum, default_value_t = Swap::Ignore)]
+    pick_order_swap: Swap,
+    #[arg(long, value_enum, default_value_t = Swap::Ignore)]
+    position_swap: Swap,
+    /// 位置偏好，靠前的位置优先，多个位置用逗号分隔（top/jungle/mid/bot/support）
+    #[arg(long, value_delimiter = ',')]
+    position_preference: Vec<String>,
+    /// 锁定英雄后从本地符文库中设置符文页
+    #[arg(long)]
+    runes: bool,
+    /// 锁定英雄后从本地出装库中写入出装方案
+    #[arg(long)]
+    item_sets: bool,
     #[arg(short = 'l', long, value_enum, default_value_t = LogLevel::Info)]
     log_level: LogLevel,
+    #[command(subcommand)]
+    command: Option<Command>,
+}
+
+/// 按名称关键字匹配已拥有的英雄
+fn match_champions(names: Vec<String>) -> Vec<Champion> {
+    let names = names.into_iter().collect::<HashSet<String>>();
+    CONTEXT
+        .champions
+        .read()
+        .unwrap()
+        .iter()
+        .filter(|c| names.iter().any(|n| c.1.contains(n)))
+        .cloned()
+        .collect()
+}
+
+/// 解析技能预设，前缀依次尝试位置、英雄名称，否则作为游戏模式
+fn parse_spell_preset(arg: &str) -> Option<SpellPreset> {
+    let (target, spells) = match arg.split_once(':') {
+        Some((target, spells)) => (Some(target), spells),
+        None => (None, arg),
+    };
+    let (spell1, spell2) = spells.split_once(',')?;
+    let mut preset = SpellPreset {
+        spell1: spell_id(spell1.trim())?,
+        spell2: spell_id(spell2.trim())?,
+        ..Default::default()
+    };
+    if let Some(target) = target {
+        if let Ok(position) = target.parse::<Position>() {
+            preset.target.position = Some(position);
+        } else if let Some(champion) = match_champions(vec![target.to_string()]).first() {
+            preset.target.champion_id = Some(champion.0);
+        } else {
+            preset.target.game_mode = Some(target.to_uppercase());
+        }
+    }
+    Some(preset)
 }
 
 async fn core(args: Cli) -> anyhow::Result<()> {
     let mut i = 0;
 
-    while CONTEXT.auto_pick.read().unwrap().unselected.is_empty() {
+    while CONTEXT.champions.read().unwrap().is_empty() {
         if i == 5 || !CONTEXT.listening.load(Ordering::Relaxed) {
             return Err(anyhow::anyhow!(""));
         }
@@ -72,30 +355,339 @@ async fn core(args: Cli) -> anyhow::Result<()> {
         .auto_accepted_delay
         .store(args.accept, Ordering::Relaxed);
     info!("自动接受延迟设置为 {} 秒", args.accept);
-    let picks = args.pick.into_iter().collect::<HashSet<String>>();
-    let champions = {
-        CONTEXT
-            .auto_pick
-            .write()
-            .unwrap()
-            .unselected
-            .extract_if(.., |c| picks.iter().any(|p| c.1.contains(p)))
-            .collect::<Vec<_>>()
-    };
-    if champions.is_empty() {
-        warn!("没有找到匹配的英雄，自动选择功能已禁用");
-    } else {
-        let auto_pick = &mut CONTEXT.auto_pick.write().unwrap();
+    CONTEXT
+        .auto_reconnect
+        .store(!args.no_reconnect, Ordering::Relaxed);
+    CONTEXT
+        .analysis_games
+        .store(args.analysis_games, Ordering::Relaxed);
+    CONTEXT
+        .retention_days
+        .store(args.retention_days, Ordering::Relaxed);
+    CONTEXT
+        .combine_analysis
+        .store(args.combine_analysis, Ordering::Relaxed);
+    CONTEXT
+        .export_markdown
+        .store(args.post_game_markdown, Ordering::Relaxed);
+    {
+        let mut outputs = CONTEXT.outputs.write().unwrap();
+        outputs.team_analysis = Output::to_sinks(&args.team_output);
+        outputs.team_analysis.chat |= args.send_analytics;
+        outputs.lobby_report = Output::to_sinks(&args.report_output);
+        outputs.post_game_report = Output::to_sinks(&args.post_game_output);
+    }
+    {
+        let mut templates = CONTEXT.analysis_templates.write().unwrap();
+        templates.language = args.template_lang.clone();
+        if let Some(path) = &args.template {
+            match std::fs::read_to_string(path) {
+                Ok(source) => {
+                    if let Err(e) = templates.set::<PlayerScore>(&args.template_lang, &source) {
+                        warn!("分析模板无效，使用默认模板: {e}");
+                    }
+                }
+                Err(e) => warn!("读取分析模板失败，使用默认模板: {e}"),
+            }
+        }
+        info!("分析消息语言: {}", templates.language);
+    }
+    let mut picks = BTreeMap::<Option<Position>, Vec<String>>::new();
+    for pick in args.pick {
+        let position = pick
+            .split_once(':')
+            .and_then(|(position, name)| Some((position.parse::<Position>().ok()?, name)));
+        match position {
+            Some((position, name)) => picks
+                .entry(Some(position))
+                .or_default()
+                .push(name.to_string()),
+            None => picks.entry(None).or_default().push(pick),
+        }
+    }
+    let mut enabled = false;
+    for (position, names) in picks {
+        let champions = match_champions(names);
+        let label = position.map_or("通用", Position::name);
         champions.iter().for_each(|champ| {
-            info!("自动选择已启用: {}", champ.1);
+            info!("自动选择已启用({label}): {}", champ.1);
         });
+        enabled |= !champions.is_empty();
+        let mut profiles = CONTEXT.profiles.write().unwrap();
+        let auto_pick = &mut profiles.active_mut().auto_pick;
+        match position {
+            Some(position) => auto_pick
+                .positions
+                .entry(position)
+                .or_default()
+                .extend(champions),
+            None => auto_pick.selected.extend(champions),
+        }
+    }
+    if enabled {
+        let mut profiles = CONTEXT.profiles.write().unwrap();
+        let auto_pick = &mut profiles.active_mut().auto_pick;
         auto_pick.enabled = true;
-        auto_pick.selected.extend(champions);
+        auto_pick.lock_mode = args.lock.to_mode();
+        auto_pick.lock_seconds = args.lock_seconds;
+        auto_pick.declare_intent = args.intent;
+        auto_pick.bench.reroll_below = args.reroll_below;
+        auto_pick.bench.cooldown_seconds = args.swap_cooldown;
+        info!("锁定方式: {}", auto_pick.lock_mode.name());
+    } else {
+        warn!("没有找到匹配的英雄，自动选择功能已禁用");
+    }
+
+    let champions = match_champions(args.ban);
+    if !champions.is_empty() {
+        let mut profiles = CONTEXT.profiles.write().unwrap();
+        let auto_ban = &mut profiles.active_mut().auto_ban;
+        champions.iter().for_each(|champ| {
+            info!("自动禁用已启用: {}", champ.1);
+        });
+        auto_ban.enabled = true;
+        auto_ban.wait_seconds = args.ban_wait;
+        auto_ban.selected.extend(champions);
+    }
+
+    {
+        let mut profiles = CONTEXT.profiles.write().unwrap();
+        let profile = profiles.active_mut();
+        for position in &args.position_preference {
+            match position.parse::<Position>() {
+                Ok(position) => profile.auto_pick.position_preference.push(position),
+                Err(e) => warn!("无法解析位置偏好: {e}"),
+            }
+        }
+        let swaps = &mut profile.swaps;
+        swaps.trade = args.trade.to_rule();
+        swaps.pick_order = args.pick_order_swap.to_rule();
+        swaps.position = args.position_swap.to_rule();
+        info!(
+            "交换请求处理: 英雄({}) 选择顺序({}) 位置({})",
+            swaps.trade.name(),
+            swaps.pick_order.name(),
+            swaps.position.name()
+        );
+    }
+
+    let mut loadout = CONTEXT.loadout.write().unwrap();
+    for arg in args.spells {
+        match parse_spell_preset(&arg) {
+            Some(preset) => loadout.spells.push(preset),
+            None => warn!("无法解析召唤师技能: {arg}"),
+        }
+    }
+    if let Some(skin) = &args.skin {
+        loadout.skin_strategy = skin.to_strategy();
+    }
+    loadout.runes = args.runes;
+    loadout.item_sets = args.item_sets;
+    if !loadout.spells.is_empty() || args.skin.is_some() || args.runes || args.item_sets {
+        loadout.enabled = true;
+        info!(
+            "自动设置技能与皮肤已启用: {} 个技能预设, {}",
+            loadout.spells.len(),
+            loadout.skin_strategy.name()
+        );
     }
 
     Ok(())
 }
 
+/// 执行一次性子命令，不启动事件监听
+async fn run_command(command: Command) -> anyhow::Result<()> {
+    match command {
+        Command::Template { file } => {
+            let source = match file {
+                Some(path) => std::fs::read_to_string(path)?,
+                None => CONTEXT
+                    .analysis_templates
+                    .read()
+                    .unwrap()
+                    .current()
+                    .to_string(),
+            };
+            let template = Template::parse(&source, PlayerScore::PLACEHOLDERS)?;
+            info!("模板预览:\n{}", template.render(&PlayerScore::sample()));
+            return Ok(());
+        }
+        Command::Runes {
+            action: LibraryAction::List,
+        } => {
+            library::load::<RunePage>(RUNES).iter().for_each(|page| {
+                info!("{}: {:?}", page.name, page.target);
+            });
+            return Ok(());
+        }
+        Command::ItemSets {
+            action: LibraryAction::List,
+        } => {
+            library::load::<ItemSet>(ITEM_SETS).iter().for_each(|set| {
+                info!("{}: {:?}", set.title, set.target);
+            });
+            return Ok(());
+        }
+        Command::PostGame { limit, markdown } => {
+            let reports = PostGameReport::history(limit)?;
+            if reports.is_empty() {
+                info!("暂无赛后报告");
+            }
+            for report in reports {
+                info!("{}\n{report}", report.title());
+                if markdown {
+                    info!("已导出到 {}", report.export_markdown()?.display());
+                }
+            }
+            return Ok(());
+        }
+        Command::Notes { action } => {
+            run_note_action(action).await?;
+            return Ok(());
+        }
+        _ => {}
+    }
+
+    let mut lcu = LcuClient::default();
+    lcu.meta.refresh()?;
+    // 出装方案按召唤师ID保存
+    lcu.update_summoner_info().await?;
+    match command {
+        Command::Runes {
+            action: LibraryAction::Import,
+        } => {
+            let count = lcu.import_rune_pages().await?;
+            info!(
+                "已导入 {count} 个符文页到 {}",
+                library::data_dir().join(RUNES).display()
+            );
+        }
+        Command::Runes {
+            action: LibraryAction::Export { name },
+        } => {
+            lcu.export_rune_page(&name).await?;
+            info!("已导出符文页: {name}");
+        }
+        Command::ItemSets {
+            action: LibraryAction::Import,
+        } => {
+            let count = lcu.import_item_sets().await?;
+            info!(
+                "已导入 {count} 个出装方案到 {}",
+                library::data_dir().join(ITEM_SETS).display()
+            );
+        }
+        Command::ItemSets {
+            action: LibraryAction::Export { name },
+        } => {
+            lcu.export_item_set(&name).await?;
+            info!("已导出出装方案: {name}");
+        }
+        Command::History {
+            puuid,
+            riot_id,
+            queue,
+            count,
+            format,
+            output,
+            details,
+        } => {
+            let puuid = match (puuid, riot_id) {
+                (Some(puuid), _) => puuid,
+                (None, Some(riot_id)) => lcu.find_summoner(&riot_id).await?.puuid,
+                (None, None) => CONTEXT.me.read().unwrap().puuid.clone(),
+            };
+            let summaries = lcu.match_history(&puuid, queue, count).await?;
+            let path = output.unwrap_or_else(|| format!("history.{}", format.extension()).into());
+            history::write_summaries(&path, format, &summaries)?;
+            info!("已导出{}场对局到 {}", summaries.len(), path.display());
+            if details {
+                let mut games = vec![];
+                for summary in &summaries {
+                    match lcu.get_game_data(summary.game_id).await {
+                        Ok(game) => games.push(game),
+                        Err(e) => warn!("查询对局详情失败 {}: {e}", summary.game_id),
+                    }
+                }
+                let path = history::details_path(&path);
+                history::write_details(&path, &games)?;
+                info!("已导出{}场对局详情到 {}", games.len(), path.display());
+            }
+        }
+        Command::Template { .. }
+        | Command::PostGame { .. }
+        | Command::Notes { .. }
+        | Command::Runes {
+            action: LibraryAction::List,
+        }
+        | Command::ItemSets {
+            action: LibraryAction::List,
+        } => unreachable!(),
+    }
+    Ok(())
+}
+
+/// 管理玩家标记，只有通过 Riot ID 添加时需要连接客户端
+async fn run_note_action(action: NoteAction) -> anyhow::Result<()> {
+    match action {
+        NoteAction::List => {
+            let notes = NOTES.all();
+            if notes.is_empty() {
+                info!("暂无玩家标记");
+            }
+            for note in notes {
+                info!("{}: {note}", note.puuid);
+            }
+        }
+        NoteAction::Add {
+            player,
+            tags,
+            note,
+            block,
+        } => {
+            let (puuid, name) = if player.contains('#') {
+                let mut lcu = LcuClient::default();
+                lcu.meta.refresh()?;
+                let summoner = lcu.find_summoner(&player).await?;
+                (summoner.puuid, player)
+            } else {
+                (player, String::new())
+            };
+            let mut entry = NOTES.get(&puuid).unwrap_or(PlayerNote {
+                puuid,
+                name,
+                ..Default::default()
+            });
+            entry.merge(PlayerNote {
+                tags: PlayerNote::parse_tags(&tags),
+                ..Default::default()
+            });
+            if !note.is_empty() {
+                entry.note = note;
+            }
+            entry.blocked |= block;
+            info!("已保存: {entry}");
+            NOTES.save(entry)?;
+        }
+        NoteAction::Remove { puuid } => {
+            if NOTES.remove(&puuid)? {
+                info!("已删除玩家标记: {puuid}");
+            } else {
+                warn!("未找到玩家标记: {puuid}");
+            }
+        }
+        NoteAction::Import { file } => {
+            let count = notes::import_notes(&file)?;
+            info!("已导入{count}条玩家标记");
+        }
+        NoteAction::Export { file } => {
+            let count = notes::export_notes(&file)?;
+            info!("已导出{count}条玩家标记到 {}", file.display());
+        }
+    }
+    Ok(())
+}
+
 #[tokio::main]
 async fn main() {
     let args = Cli::parse();
@@ -123,6 +715,13 @@ async fn main() {
         .unwrap();
     init_config(config).unwrap();
 
+    if let Some(command) = args.command {
+        run_command(command)
+            .await
+            .unwrap_or_else(|e| error!("执行命令失败: {e}"));
+        return;
+    }
+
     let lcu = Arc::new(RwLock::new(lcu_backend::LcuClient::default()));
     let cancel_token = Arc::new(CancellationToken::new());
 
@@ -9,27 +9,52 @@ use eframe::egui::{
     self, Align, Checkbox, Color32, CursorIcon, DragValue, FontData, FontDefinitions, Frame, Grid,
     Id, Label, Layout, Modal, ScrollArea, Separator, Vec2, Widget,
 };
-use log::error;
+use log::{error, info};
 use rfd::FileDialog;
 use tokio::sync::RwLock;
 use tokio_util::sync::CancellationToken;
 
 use crate::log::LOGS;
-use lcu_backend::{CONTEXT, LcuClient, start_event_listener};
+use lcu_backend::{
+    CONTEXT, Champion, DEFAULT_ANALYSIS_GAMES, LcuClient, LockMode, PickProfile, PickProfiles,
+    PlayerScore, Position, PostGameReport, SUMMONER_SPELLS, SkinStrategy, SpellPreset, SwapRule,
+    SwapRules,
+    notes::{NOTES, PlayerNote, export_notes, import_notes},
+    output::{Feature, OutputSettings, Sink},
+    spell_name, start_event_listener,
+    store::DEFAULT_RETENTION_DAYS,
+    template::{AnalysisTemplates, Template, TemplateData},
+};
 
 // 应用常量
 const FRAME_MARGIN: f32 = 5.0;
 const SEPARATOR_SPACING: f32 = 10.0;
 const BUTTON_SPACING: f32 = 30.0;
 const CHAMPION_FRAME_SIZE: Vec2 = Vec2::new(200.0, 200.0);
+// 赛后报告历史窗口显示的报告数
+const POST_GAME_HISTORY: usize = 50;
 
 /// 主应用程序状态
 pub struct MyApp {
     client: Arc<RwLock<LcuClient>>,
     rt: tokio::runtime::Runtime,
     cancel_token: Arc<CancellationToken>,
-    // 英雄选择窗口是否打开
-    champion_pick_window_open: bool,
+    // 英雄选择窗口正在编辑的列表，None表示窗口关闭
+    champion_window: Option<ChampionList>,
+    // 方案编辑窗口，None表示窗口关闭
+    profile_editor: Option<ProfileEditor>,
+    // 召唤师技能和皮肤设置窗口是否打开
+    loadout_window: bool,
+    // 分析模板编辑窗口，None表示窗口关闭
+    template_editor: Option<TemplateEditor>,
+    // 分析结果窗口是否打开
+    report_window: bool,
+    // 输出位置设置窗口是否打开
+    output_window: bool,
+    // 赛后报告历史窗口，None表示窗口关闭
+    post_game_history: Option<PostGameHistory>,
+    // 玩家标记窗口，None表示窗口关闭
+    note_editor: Option<NoteEditor>,
     // modal是否打开
     modal_open: bool,
     // 搜索关键词
@@ -48,13 +73,6 @@ macro_rules! move_item_safe {
             $dst.insert(insert_pos, item);
         }
     };
-    // 移动到开头
-    ($src:expr, $dst:expr, $from:expr) => {
-        if $from < $src.len() {
-            let item = $src.remove($from);
-            $dst.insert(0, item);
-        }
-    };
 }
 
 /// 渲染英雄列表的通用宏
@@ -80,12 +98,76 @@ impl App for MyApp {
 
         egui::CentralPanel::default().show(ctx, |ui| self.log_panel(ctx, ui));
 
-        if self.champion_pick_window_open {
+        if let Some(list) = self.champion_window {
             egui::Window::new("select")
                 .collapsible(false)
                 .title_bar(false)
                 .resizable([false, true])
-                .show(ctx, |ui| self.champion_pick_window(ui));
+                .show(ctx, |ui| self.champion_pick_window(ui, list));
+        }
+
+        if self.profile_editor.is_some() {
+            egui::Window::new("profile")
+                .collapsible(false)
+                .title_bar(false)
+                .resizable(false)
+                .show(ctx, |ui| self.profile_editor_window(ui));
+        }
+
+        if self.template_editor.is_some() {
+            egui::Window::new("template")
+                .collapsible(false)
+                .title_bar(false)
+                .resizable(false)
+                .show(ctx, |ui| self.template_editor_window(ui));
+        }
+
+        if self.loadout_window {
+            egui::Window::new("loadout")
+                .collapsible(false)
+                .title_bar(false)
+                .resizable(false)
+                .show(ctx, |ui| self.loadout_editor_window(ui));
+        }
+
+        if self.report_window {
+            egui::Window::new("report")
+                .collapsible(false)
+                .title_bar(false)
+                .resizable(false)
+                .show(ctx, |ui| self.analysis_report_window(ui));
+        }
+
+        if self.output_window {
+            egui::Window::new("output")
+                .collapsible(false)
+                .title_bar(false)
+                .resizable(false)
+                .show(ctx, |ui| self.output_settings_window(ui));
+        }
+
+        if self.post_game_history.is_some() {
+            egui::Window::new("post_game")
+                .collapsible(false)
+                .title_bar(false)
+                .resizable(false)
+                .show(ctx, |ui| self.post_game_history_window(ui));
+        }
+
+        if self.note_editor.is_some() {
+            egui::Window::new("notes")
+                .collapsible(false)
+                .title_bar(false)
+                .resizable(false)
+                .show(ctx, |ui| self.note_editor_window(ui));
+        }
+
+        if !CONTEXT.player_alerts.read().unwrap().is_empty() {
+            egui::Window::new("alerts")
+                .collapsible(false)
+                .title_bar(false)
+                .resizable(false)
+                .show(ctx, |ui| self.player_alerts_window(ui));
         }
     }
 
@@ -96,16 +178,48 @@ impl App for MyApp {
             serde_json::to_string(&*CONTEXT.me.read().unwrap()).unwrap(),
         );
         storage.set_string(
-            "auto_pick",
-            serde_json::to_string(&*CONTEXT.auto_pick.read().unwrap()).unwrap(),
+            "champions",
+            serde_json::to_string(&*CONTEXT.champions.read().unwrap()).unwrap(),
+        );
+        storage.set_string(
+            "profiles",
+            serde_json::to_string(&*CONTEXT.profiles.read().unwrap()).unwrap(),
+        );
+        storage.set_string(
+            "loadout",
+            serde_json::to_string(&*CONTEXT.loadout.read().unwrap()).unwrap(),
         );
         storage.set_string(
             "auto_accepted_delay",
             serde_json::to_string(&CONTEXT.auto_accepted_delay.load(Ordering::Relaxed)).unwrap(),
         );
         storage.set_string(
-            "auto_send_analysis",
-            serde_json::to_string(&CONTEXT.auto_send_analysis.load(Ordering::Relaxed)).unwrap(),
+            "outputs",
+            serde_json::to_string(&*CONTEXT.outputs.read().unwrap()).unwrap(),
+        );
+        storage.set_string(
+            "combine_analysis",
+            serde_json::to_string(&CONTEXT.combine_analysis.load(Ordering::Relaxed)).unwrap(),
+        );
+        storage.set_string(
+            "analysis_games",
+            serde_json::to_string(&CONTEXT.analysis_games.load(Ordering::Relaxed)).unwrap(),
+        );
+        storage.set_string(
+            "export_markdown",
+            serde_json::to_string(&CONTEXT.export_markdown.load(Ordering::Relaxed)).unwrap(),
+        );
+        storage.set_string(
+            "retention_days",
+            serde_json::to_string(&CONTEXT.retention_days.load(Ordering::Relaxed)).unwrap(),
+        );
+        storage.set_string(
+            "analysis_templates",
+            serde_json::to_string(&*CONTEXT.analysis_templates.read().unwrap()).unwrap(),
+        );
+        storage.set_string(
+            "auto_reconnect",
+            serde_json::to_string(&CONTEXT.auto_reconnect.load(Ordering::Relaxed)).unwrap(),
         );
         storage.set_string(
             "game_client_path",
@@ -114,11 +228,189 @@ impl App for MyApp {
     }
 }
 
+/// 英雄选择窗口可编辑的列表，选择列表可按位置区分，None为通用列表
+#[derive(Clone, Copy)]
+enum ChampionList {
+    Pick(Option<Position>),
+    Ban,
+}
+
+fn position_name(position: Option<Position>) -> &'static str {
+    position.map_or("通用", Position::name)
+}
+
+impl ChampionList {
+    fn header(self) -> String {
+        match self {
+            ChampionList::Pick(position) => format!("{}英雄", position_name(position)),
+            ChampionList::Ban => "禁用英雄".to_string(),
+        }
+    }
+
+    /// 在持有写锁的情况下操作当前方案中对应的英雄列表
+    fn with_selected<R>(self, f: impl FnOnce(&mut Vec<Champion>) -> R) -> R {
+        let mut profiles = CONTEXT.profiles.write().unwrap();
+        let profile = profiles.active_mut();
+        match self {
+            ChampionList::Pick(None) => f(&mut profile.auto_pick.selected),
+            ChampionList::Pick(Some(position)) => {
+                f(profile.auto_pick.positions.entry(position).or_default())
+            }
+            ChampionList::Ban => f(&mut profile.auto_ban.selected),
+        }
+    }
+}
+
+/// 方案编辑窗口的输入内容，保存时写回当前方案
+struct ProfileEditor {
+    name: String,
+    queue_ids: String,
+    game_modes: String,
+    swaps: SwapRules,
+    position_preference: String,
+}
+
+impl ProfileEditor {
+    fn from_profile(profile: &PickProfile) -> Self {
+        let queue_ids = profile.queue_ids.iter().map(|id| id.to_string());
+        Self {
+            name: profile.name.clone(),
+            queue_ids: queue_ids.collect::<Vec<_>>().join(","),
+            game_modes: profile.game_modes.join(","),
+            swaps: profile.swaps,
+            position_preference: profile
+                .auto_pick
+                .position_preference
+                .iter()
+                .map(|position| position.key())
+                .collect::<Vec<_>>()
+                .join(","),
+        }
+    }
+
+    fn apply(&self, profile: &mut PickProfile) {
+        let items = |text: &str| {
+            text.split(',')
+                .map(|item| item.trim().to_string())
+                .filter(|item| !item.is_empty())
+                .collect::<Vec<_>>()
+        };
+        profile.name = self.name.trim().to_string();
+        profile.queue_ids = items(&self.queue_ids)
+            .iter()
+            .filter_map(|id| id.parse().ok())
+            .collect();
+        profile.game_modes = items(&self.game_modes)
+            .into_iter()
+            .map(|mode| mode.to_uppercase())
+            .collect();
+        profile.swaps = self.swaps;
+        profile.auto_pick.position_preference = items(&self.position_preference)
+            .iter()
+            .filter_map(|position| position.parse().ok())
+            .collect();
+    }
+}
+
+/// 正在编辑的分析模板，保存前不影响发送的消息
+struct TemplateEditor {
+    language: String,
+    source: String,
+    // 保存失败的原因
+    error: Option<String>,
+}
+
+impl TemplateEditor {
+    fn load(language: &str) -> Self {
+        let templates = CONTEXT.analysis_templates.read().unwrap();
+        Self {
+            language: language.to_string(),
+            source: templates
+                .templates
+                .get(language)
+                .cloned()
+                .unwrap_or_default(),
+            error: None,
+        }
+    }
+}
+
+/// 从本地数据库读取的赛后报告
+struct PostGameHistory {
+    reports: Vec<PostGameReport>,
+    selected: usize,
+}
+
+impl PostGameHistory {
+    fn load() -> Self {
+        let reports = PostGameReport::history(POST_GAME_HISTORY).unwrap_or_else(|e| {
+            error!("读取赛后报告失败: {e}");
+            vec![]
+        });
+        Self {
+            reports,
+            selected: 0,
+        }
+    }
+}
+
+/// 玩家标记窗口的已有备注和正在编辑的备注
+struct NoteEditor {
+    notes: Vec<PlayerNote>,
+    editing: PlayerNote,
+    // 逗号分隔的标签
+    tags: String,
+}
+
+impl NoteEditor {
+    fn load() -> Self {
+        Self {
+            notes: NOTES.all(),
+            editing: PlayerNote::default(),
+            tags: String::new(),
+        }
+    }
+
+    fn edit(&mut self, note: PlayerNote) {
+        self.tags = note.tags.join(",");
+        self.editing = note;
+    }
+}
+
+/// 最近分析过的玩家 (puuid, 名称)，用于快速添加标记
+fn recent_players() -> Vec<(String, String)> {
+    let mut players = CONTEXT
+        .team_analysis
+        .read()
+        .unwrap()
+        .iter()
+        .map(|score| (score.puuid.clone(), score.name.clone()))
+        .collect::<Vec<_>>();
+    if let Some(report) = &*CONTEXT.lobby_report.read().unwrap() {
+        for player in report.allies.iter().chain(&report.enemies) {
+            if let Some(score) = &player.score
+                && !players.iter().any(|(puuid, _)| *puuid == player.puuid)
+            {
+                players.push((player.puuid.clone(), score.name.clone()));
+            }
+        }
+    }
+    players
+}
+
+/// 导出赛后报告并在日志中显示文件位置
+fn export_post_game_markdown(report: &PostGameReport) {
+    match report.export_markdown() {
+        Ok(path) => info!("赛后报告已导出到 {}", path.display()),
+        Err(e) => error!("导出赛后报告失败: {e}"),
+    }
+}
+
 #[derive(Default)]
 struct ChampionPickState {
     drag_from: Option<usize>,
     drag_to: Option<usize>,
-    select_index: Option<usize>,
+    select_id: Option<u16>,
     unselect_index: Option<usize>,
 }
 
@@ -162,8 +454,22 @@ impl MyApp {
 
         if let Some(storage) = cc.storage {
             let ctx = &*CONTEXT;
-            *ctx.auto_pick.write().unwrap() =
-                serde_json::from_str(&storage.get_string("auto_pick").unwrap_or_default())
+            *ctx.champions.write().unwrap() =
+                serde_json::from_str(&storage.get_string("champions").unwrap_or_default())
+                    .unwrap_or_default();
+            *ctx.profiles.write().unwrap() =
+                serde_json::from_str(&storage.get_string("profiles").unwrap_or_default())
+                    .unwrap_or_else(|_| {
+                        // 兼容旧版本保存的单一选择列表
+                        let mut profiles = PickProfiles::default();
+                        profiles.active_mut().auto_pick = serde_json::from_str(
+                            &storage.get_string("auto_pick").unwrap_or_default(),
+                        )
+                        .unwrap_or_default();
+                        profiles
+                    });
+            *ctx.loadout.write().unwrap() =
+                serde_json::from_str(&storage.get_string("loadout").unwrap_or_default())
                     .unwrap_or_default();
             ctx.auto_accepted_delay.store(
                 serde_json::from_str(
@@ -174,11 +480,51 @@ impl MyApp {
                 .unwrap_or_default(),
                 Ordering::Relaxed,
             );
-            ctx.auto_send_analysis.store(
-                serde_json::from_str(&storage.get_string("auto_send_analysis").unwrap_or_default())
+            *ctx.outputs.write().unwrap() =
+                serde_json::from_str(&storage.get_string("outputs").unwrap_or_default())
+                    .unwrap_or_else(|_| {
+                        // 兼容旧版本保存的自动发送战绩分析开关
+                        let mut outputs = OutputSettings::default();
+                        if let Some(chat) = storage
+                            .get_string("auto_send_analysis")
+                            .and_then(|chat| serde_json::from_str(&chat).ok())
+                        {
+                            outputs.team_analysis.chat = chat;
+                        }
+                        outputs
+                    });
+            ctx.combine_analysis.store(
+                serde_json::from_str(&storage.get_string("combine_analysis").unwrap_or_default())
+                    .unwrap_or_default(),
+                Ordering::Relaxed,
+            );
+            ctx.analysis_games.store(
+                storage
+                    .get_string("analysis_games")
+                    .and_then(|games| serde_json::from_str(&games).ok())
+                    .unwrap_or(DEFAULT_ANALYSIS_GAMES),
+                Ordering::Relaxed,
+            );
+            ctx.export_markdown.store(
+                serde_json::from_str(&storage.get_string("export_markdown").unwrap_or_default())
                     .unwrap_or_default(),
                 Ordering::Relaxed,
             );
+            ctx.retention_days.store(
+                storage
+                    .get_string("retention_days")
+                    .and_then(|days| serde_json::from_str(&days).ok())
+                    .unwrap_or(DEFAULT_RETENTION_DAYS),
+                Ordering::Relaxed,
+            );
+            *ctx.analysis_templates.write().unwrap() =
+                serde_json::from_str(&storage.get_string("analysis_templates").unwrap_or_default())
+                    .unwrap_or_default();
+            ctx.auto_reconnect.store(
+                serde_json::from_str(&storage.get_string("auto_reconnect").unwrap_or_default())
+                    .unwrap_or(true),
+                Ordering::Relaxed,
+            );
             *ctx.me.write().unwrap() =
                 serde_json::from_str(&storage.get_string("me").unwrap_or_default())
                     .unwrap_or_default();
@@ -197,7 +543,14 @@ impl MyApp {
             rt: tokio::runtime::Runtime::new().unwrap(),
             client: Arc::new(RwLock::new(LcuClient::default())),
             cancel_token: Arc::new(CancellationToken::new()),
-            champion_pick_window_open: false,
+            champion_window: None,
+            profile_editor: None,
+            loadout_window: false,
+            template_editor: None,
+            report_window: false,
+            output_window: false,
+            post_game_history: None,
+            note_editor: None,
             modal_open: false,
             search_text: String::new(),
             game_client_path,
@@ -213,19 +566,167 @@ impl MyApp {
     /// 渲染设置控件区域
     fn render_settings_controls(&mut self, ui: &mut egui::Ui) {
         let mut auto_accepted_delay = CONTEXT.auto_accepted_delay.load(Ordering::Relaxed);
-        let mut auto_send_analysis = CONTEXT.auto_send_analysis.load(Ordering::Relaxed);
+        let mut analysis_games = CONTEXT.analysis_games.load(Ordering::Relaxed);
+        let mut retention_days = CONTEXT.retention_days.load(Ordering::Relaxed);
+        let mut combine_analysis = CONTEXT.combine_analysis.load(Ordering::Relaxed);
+        let mut export_markdown = CONTEXT.export_markdown.load(Ordering::Relaxed);
+        let mut auto_reconnect = CONTEXT.auto_reconnect.load(Ordering::Relaxed);
         ui.with_layout(Layout::top_down_justified(Align::Center), |ui| {
             ui.add_space(FRAME_MARGIN);
             Grid::new("settings_grid")
                 .num_columns(2)
                 .spacing([40.0, 4.0])
                 .show(ui, |ui| {
+                    // 当前方案，进入绑定的模式时自动切换
+                    if ui.button("编辑方案").clicked() {
+                        let profiles = CONTEXT.profiles.read().unwrap();
+                        self.profile_editor = Some(ProfileEditor::from_profile(profiles.active()));
+                    }
+                    {
+                        let mut profiles = CONTEXT.profiles.write().unwrap();
+                        let mut active = profiles.active;
+                        egui::ComboBox::from_id_salt("profile")
+                            .selected_text(profiles.active().name.clone())
+                            .show_ui(ui, |ui| {
+                                for (idx, profile) in profiles.profiles.iter().enumerate() {
+                                    ui.selectable_value(&mut active, idx, &profile.name);
+                                }
+                            });
+                        profiles.active = active;
+                    }
+                    ui.end_row();
+
                     // 英雄选择按钮
                     if ui.button("自动选择英雄").clicked() {
-                        self.champion_pick_window_open = true;
+                        self.champion_window = Some(ChampionList::Pick(None));
+                    }
+                    ui.add(Checkbox::without_text(
+                        &mut CONTEXT
+                            .profiles
+                            .write()
+                            .unwrap()
+                            .active_mut()
+                            .auto_pick
+                            .enabled,
+                    ));
+                    ui.end_row();
+
+                    // 锁定方式，延迟锁定和最后几秒锁定需要设置秒数
+                    ui.add(Label::new("锁定方式"));
+                    {
+                        let mut profiles = CONTEXT.profiles.write().unwrap();
+                        let auto_pick = &mut profiles.active_mut().auto_pick;
+                        ui.horizontal(|ui| {
+                            egui::ComboBox::from_id_salt("lock_mode")
+                                .selected_text(auto_pick.lock_mode.name())
+                                .show_ui(ui, |ui| {
+                                    for mode in LockMode::ALL {
+                                        ui.selectable_value(
+                                            &mut auto_pick.lock_mode,
+                                            mode,
+                                            mode.name(),
+                                        );
+                                    }
+                                });
+                            if matches!(
+                                auto_pick.lock_mode,
+                                LockMode::AfterDelay | LockMode::LastSeconds
+                            ) {
+                                ui.add(
+                                    DragValue::new(&mut auto_pick.lock_seconds)
+                                        .range(0.0..=30.0)
+                                        .suffix(" s"),
+                                );
+                            }
+                        });
                     }
+                    ui.end_row();
+
+                    // 计划阶段预选英雄
+                    ui.add(Label::new("声明英雄意向"));
                     ui.add(Checkbox::without_text(
-                        &mut CONTEXT.auto_pick.write().unwrap().enabled,
+                        &mut CONTEXT
+                            .profiles
+                            .write()
+                            .unwrap()
+                            .active_mut()
+                            .auto_pick
+                            .declare_intent,
+                    ));
+                    ui.end_row();
+
+                    // 大乱斗备选席，当前英雄不在列表前N位时重随
+                    ui.add(Label::new("大乱斗重随阈值"));
+                    ui.add(
+                        DragValue::new(
+                            &mut CONTEXT
+                                .profiles
+                                .write()
+                                .unwrap()
+                                .active_mut()
+                                .auto_pick
+                                .bench
+                                .reroll_below,
+                        )
+                        .range(0.0..=20.0),
+                    );
+                    ui.end_row();
+
+                    ui.add(Label::new("大乱斗交换间隔"));
+                    ui.add(
+                        DragValue::new(
+                            &mut CONTEXT
+                                .profiles
+                                .write()
+                                .unwrap()
+                                .active_mut()
+                                .auto_pick
+                                .bench
+                                .cooldown_seconds,
+                        )
+                        .range(0.0..=10.0)
+                        .suffix(" s"),
+                    );
+                    ui.end_row();
+
+                    // 英雄禁用按钮
+                    if ui.button("自动禁用英雄").clicked() {
+                        self.champion_window = Some(ChampionList::Ban);
+                    }
+                    ui.add(Checkbox::without_text(
+                        &mut CONTEXT
+                            .profiles
+                            .write()
+                            .unwrap()
+                            .active_mut()
+                            .auto_ban
+                            .enabled,
+                    ));
+                    ui.end_row();
+
+                    // 禁用阶段剩余时间小于该值时才禁用
+                    ui.add(Label::new("禁用等待剩余时间"));
+                    ui.add(
+                        DragValue::new(
+                            &mut CONTEXT
+                                .profiles
+                                .write()
+                                .unwrap()
+                                .active_mut()
+                                .auto_ban
+                                .wait_seconds,
+                        )
+                        .range(0.0..=30.0)
+                        .suffix(" s"),
+                    );
+                    ui.end_row();
+
+                    // 锁定英雄后设置召唤师技能和皮肤
+                    if ui.button("技能与皮肤").clicked() {
+                        self.loadout_window = true;
+                    }
+                    ui.add(Checkbox::without_text(
+                        &mut CONTEXT.loadout.write().unwrap().enabled,
                     ));
                     ui.end_row();
 
@@ -243,13 +744,80 @@ impl MyApp {
                     }
                     ui.end_row();
 
-                    // 自动分析
-                    ui.add(Label::new("自动发送分析"));
-                    let check_box_resp = ui.add(Checkbox::without_text(&mut auto_send_analysis));
-                    if check_box_resp.changed() {
+                    // 分析消息模板和各功能的输出位置
+                    if ui.button("分析模板").clicked() {
+                        let language = CONTEXT.analysis_templates.read().unwrap().language.clone();
+                        self.template_editor = Some(TemplateEditor::load(&language));
+                    }
+                    if ui.button("输出位置").clicked() {
+                        self.output_window = true;
+                    }
+                    ui.end_row();
+
+                    // 标记过的玩家出现在英雄选择或对局中时提醒
+                    if ui.button("玩家标记").clicked() {
+                        self.note_editor = Some(NoteEditor::load());
+                    }
+                    ui.end_row();
+
+                    // 所有队友的分析合并为一条消息
+                    ui.add(Label::new("合并分析消息"));
+                    if ui
+                        .add(Checkbox::without_text(&mut combine_analysis))
+                        .changed()
+                    {
+                        CONTEXT
+                            .combine_analysis
+                            .store(combine_analysis, Ordering::Relaxed);
+                    }
+                    ui.end_row();
+
+                    // 每位玩家分析的对局数，只统计同一队列且非重开的对局
+                    ui.add(Label::new("分析对局数"));
+                    if ui
+                        .add(DragValue::new(&mut analysis_games).range(5.0..=50.0))
+                        .changed()
+                    {
+                        CONTEXT
+                            .analysis_games
+                            .store(analysis_games, Ordering::Relaxed);
+                    }
+                    ui.end_row();
+
+                    // 赛后报告同时导出到 data/reports/post_game
+                    ui.add(Label::new("导出赛后报告"));
+                    if ui
+                        .add(Checkbox::without_text(&mut export_markdown))
+                        .changed()
+                    {
+                        CONTEXT
+                            .export_markdown
+                            .store(export_markdown, Ordering::Relaxed);
+                    }
+                    ui.end_row();
+
+                    // 本地对局数据库的保留时间，连接客户端时清理
+                    ui.add(Label::new("对局保留天数"));
+                    if ui
+                        .add(DragValue::new(&mut retention_days).range(0.0..=365.0))
+                        .on_hover_text("0表示永久保留")
+                        .changed()
+                    {
+                        CONTEXT
+                            .retention_days
+                            .store(retention_days, Ordering::Relaxed);
+                    }
+                    ui.end_row();
+
+                    // 掉线后自动重新连接
+                    ui.add(Label::new("自动重新连接"));
+                    if ui
+                        .add(Checkbox::without_text(&mut auto_reconnect))
+                        .changed()
+                    {
                         CONTEXT
-                            .auto_send_analysis
-                            .store(auto_send_analysis, Ordering::Relaxed);
+                            .auto_reconnect
+                            .store(auto_reconnect, Ordering::Relaxed);
                     }
                 });
         });
@@ -327,6 +895,10 @@ impl MyApp {
                         CONTEXT.reset();
                     }
                 }
+
+                if ui.button("分析结果").clicked() {
+                    self.report_window = true;
+                }
             },
         );
     }
@@ -348,9 +920,22 @@ impl MyApp {
             });
     }
 
-    fn champion_pick_window(&mut self, ui: &mut egui::Ui) {
+    fn champion_pick_window(&mut self, ui: &mut egui::Ui, list: ChampionList) {
         let mut state = ChampionPickState::default();
+        let selected = list.with_selected(|selected| selected.clone());
         ui.horizontal(|ui| {
+            // 选择列表可切换位置，列表为空时使用通用列表
+            if let ChampionList::Pick(mut position) = list {
+                egui::ComboBox::from_id_salt("position")
+                    .selected_text(position_name(position))
+                    .show_ui(ui, |ui| {
+                        ui.selectable_value(&mut position, None, position_name(None));
+                        for p in Position::ALL {
+                            ui.selectable_value(&mut position, Some(p), p.name());
+                        }
+                    });
+                self.champion_window = Some(ChampionList::Pick(position));
+            }
             ui.with_layout(Layout::right_to_left(Align::Center), |ui| {
                 ui.text_edit_singleline(&mut self.search_text);
                 ui.label("搜索：");
@@ -363,13 +948,12 @@ impl MyApp {
 
             // 左侧：未选中英雄列表
             render_champion_list!(left, "可用英雄", |ui| {
-                for (idx, champion) in CONTEXT
-                    .auto_pick
+                for champion in CONTEXT
+                    .champions
                     .read()
                     .unwrap()
-                    .unselected
                     .iter()
-                    .enumerate()
+                    .filter(|champion| !selected.iter().any(|c| c.0 == champion.0))
                 {
                     let name = champion.1.clone();
                     if name.contains(&self.search_text)
@@ -378,39 +962,32 @@ impl MyApp {
                             .on_hover_cursor(CursorIcon::PointingHand)
                             .clicked()
                     {
-                        state.select_index = Some(idx);
+                        state.select_id = Some(champion.0);
                     }
                 }
             });
             // 右侧：已选中英雄列表（支持拖拽）
-            render_champion_list!(right, "已选英雄", |ui| {
-                for (idx, name) in CONTEXT
-                    .auto_pick
-                    .read()
-                    .unwrap()
-                    .selected
-                    .iter()
-                    .enumerate()
-                {
-                    self.render_draggable_champion_item(ui, idx, &name.1, &mut state);
+            // 英雄选择中不可用的英雄显示跳过原因
+            let unavailable = CONTEXT.unavailable_champions.read().unwrap().clone();
+            render_champion_list!(right, list.header(), |ui| {
+                for (idx, champion) in selected.iter().enumerate() {
+                    let name = match unavailable.get(&champion.0) {
+                        Some(reason) => format!("{}（{}）", champion.1, reason.name()),
+                        None => champion.1.clone(),
+                    };
+                    self.render_draggable_champion_item(ui, idx, &name, &mut state);
                 }
             });
         });
 
         // 处理所有操作
-        self.handle_champion_operations(state);
+        Self::handle_champion_operations(list, state);
 
         // 控制按钮
         ui.separator();
         ui.horizontal(|ui| {
             if ui.button("清空已选").clicked() {
-                let mut auto_pick = CONTEXT.auto_pick.write().unwrap();
-                let selected_clone = auto_pick.selected.clone();
-                auto_pick.unselected.extend(selected_clone);
-                auto_pick
-                    .unselected
-                    .sort_by_key(|champion| champion.1.clone());
-                auto_pick.selected.clear();
+                list.with_selected(|selected| selected.clear());
             }
             if ui.button("更新可用英雄").clicked() {
                 if !CONTEXT.listening.load(Ordering::Relaxed) {
@@ -427,26 +1004,14 @@ impl MyApp {
                                 error!("获取英雄列表失败: {e}");
                                 vec![]
                             });
-                        let selected = CONTEXT
-                            .auto_pick
-                            .read()
-                            .unwrap()
-                            .selected
-                            .iter()
-                            .map(|champ| champ.0)
-                            .collect::<Vec<u16>>();
-                        let mut auto_pick = CONTEXT.auto_pick.write().unwrap();
-                        auto_pick.unselected = champions
-                            .into_iter()
-                            .filter(|champ| !selected.contains(&champ.0))
-                            .collect();
+                        *CONTEXT.champions.write().unwrap() = champions;
                     });
                 }
             }
 
             ui.with_layout(Layout::right_to_left(Align::Center), |ui| {
                 if ui.button("关闭").clicked() {
-                    self.champion_pick_window_open = false;
+                    self.champion_window = None;
                 }
             });
         });
@@ -456,6 +1021,579 @@ impl MyApp {
         }
     }
 
+    /// 编辑当前方案的名称及绑定的队列和模式
+    fn profile_editor_window(&mut self, ui: &mut egui::Ui) {
+        let Some(editor) = &mut self.profile_editor else {
+            return;
+        };
+        Grid::new("profile_grid")
+            .num_columns(2)
+            .spacing([20.0, 4.0])
+            .show(ui, |ui| {
+                ui.label("方案名称");
+                ui.text_edit_singleline(&mut editor.name);
+                ui.end_row();
+                ui.label("队列ID");
+                ui.add(egui::TextEdit::singleline(&mut editor.queue_ids).hint_text("420,440"));
+                ui.end_row();
+                ui.label("游戏模式");
+                ui.add(
+                    egui::TextEdit::singleline(&mut editor.game_modes).hint_text("CLASSIC,ARAM"),
+                );
+                ui.end_row();
+                for (label, rule) in [
+                    ("交换英雄", &mut editor.swaps.trade),
+                    ("交换选择顺序", &mut editor.swaps.pick_order),
+                    ("交换位置", &mut editor.swaps.position),
+                ] {
+                    ui.label(label);
+                    egui::ComboBox::from_id_salt(label)
+                        .selected_text(rule.name())
+                        .show_ui(ui, |ui| {
+                            for option in SwapRule::ALL {
+                                ui.selectable_value(rule, option, option.name());
+                            }
+                        });
+                    ui.end_row();
+                }
+                ui.label("位置偏好");
+                ui.add(
+                    egui::TextEdit::singleline(&mut editor.position_preference)
+                        .hint_text("middle,jungle"),
+                )
+                .on_hover_text("靠前的位置优先，智能处理交换位置请求时使用");
+                ui.end_row();
+            });
+
+        ui.separator();
+        let mut close = false;
+        ui.horizontal(|ui| {
+            let mut profiles = CONTEXT.profiles.write().unwrap();
+            if ui.button("保存").clicked() {
+                editor.apply(profiles.active_mut());
+            }
+            if ui.button("新建方案").clicked() {
+                profiles.profiles.push(PickProfile::new("新方案"));
+                profiles.active = profiles.profiles.len() - 1;
+                *editor = ProfileEditor::from_profile(profiles.active());
+            }
+            // 默认方案不可删除
+            if ui
+                .add_enabled(profiles.active != 0, egui::Button::new("删除方案"))
+                .clicked()
+            {
+                let active = profiles.active;
+                profiles.profiles.remove(active);
+                profiles.active = 0;
+                *editor = ProfileEditor::from_profile(profiles.active());
+            }
+            ui.with_layout(Layout::right_to_left(Align::Center), |ui| {
+                if ui.button("关闭").clicked() {
+                    close = true;
+                }
+            });
+        });
+        if close {
+            self.profile_editor = None;
+        }
+    }
+
+    /// 编辑召唤师技能预设、皮肤选择方式、符文页和出装方案，英雄/模式/位置为空时匹配任意值
+    fn loadout_editor_window(&mut self, ui: &mut egui::Ui) {
+        let mut loadout = CONTEXT.loadout.write().unwrap();
+        let champions = CONTEXT.champions.read().unwrap();
+        ui.horizontal(|ui| {
+            ui.label("皮肤");
+            egui::ComboBox::from_id_salt("skin_strategy")
+                .selected_text(loadout.skin_strategy.name())
+                .show_ui(ui, |ui| {
+                    for strategy in SkinStrategy::ALL {
+                        ui.selectable_value(&mut loadout.skin_strategy, strategy, strategy.name());
+                    }
+                });
+        });
+        ui.horizontal(|ui| {
+            ui.checkbox(&mut loadout.item_sets, "自动写入出装方案");
+            if ui.button("导入客户端出装方案").clicked() {
+                let client = self.client.clone();
+                self.rt.spawn(async move {
+                    match client.read().await.import_item_sets().await {
+                        Ok(count) => info!("已导入 {count} 个出装方案"),
+                        Err(e) => error!("导入出装方案失败: {e}"),
+                    }
+                });
+            }
+        });
+        ui.horizontal(|ui| {
+            ui.checkbox(&mut loadout.runes, "自动设置符文页");
+            if ui.button("导入客户端符文页").clicked() {
+                let client = self.client.clone();
+                self.rt.spawn(async move {
+                    match client.read().await.import_rune_pages().await {
+                        Ok(count) => info!("已导入 {count} 个符文页"),
+                        Err(e) => error!("导入符文页失败: {e}"),
+                    }
+                });
+            }
+        });
+        ui.separator();
+
+        let mut remove = None;
+        Grid::new("spell_grid")
+            .num_columns(6)
+            .spacing([8.0, 4.0])
+            .show(ui, |ui| {
+                for label in ["英雄", "模式", "位置", "D", "F", ""] {
+                    ui.label(label);
+                }
+                ui.end_row();
+                for (idx, preset) in loadout.spells.iter_mut().enumerate() {
+                    let champion_name = preset.target.champion_id.map_or("任意", |id| {
+                        champions
+                            .iter()
+                            .find(|c| c.0 == id)
+                            .map_or("未知", |c| c.1.as_str())
+                    });
+                    egui::ComboBox::from_id_salt(("spell_champion", idx))
+                        .selected_text(champion_name)
+                        .show_ui(ui, |ui| {
+                            ui.selectable_value(&mut preset.target.champion_id, None, "任意");
+                            for champion in champions.iter() {
+                                ui.selectable_value(
+                                    &mut preset.target.champion_id,
+                                    Some(champion.0),
+                                    &champion.1,
+                                );
+                            }
+                        });
+                    let mut game_mode = preset.target.game_mode.clone().unwrap_or_default();
+                    if ui
+                        .add(
+                            egui::TextEdit::singleline(&mut game_mode)
+                                .hint_text("任意")
+                                .desired_width(60.0),
+                        )
+                        .changed()
+                    {
+                        let game_mode = game_mode.trim();
+                        preset.target.game_mode =
+                            (!game_mode.is_empty()).then(|| game_mode.to_string());
+                    }
+                    egui::ComboBox::from_id_salt(("spell_position", idx))
+                        .selected_text(preset.target.position.map_or("任意", Position::name))
+                        .show_ui(ui, |ui| {
+                            ui.selectable_value(&mut preset.target.position, None, "任意");
+                            for position in Position::ALL {
+                                ui.selectable_value(
+                                    &mut preset.target.position,
+                                    Some(position),
+                                    position.name(),
+                                );
+                            }
+                        });
+                    for (slot, spell) in [(1, &mut preset.spell1), (2, &mut preset.spell2)] {
+                        egui::ComboBox::from_id_salt(("spell", idx, slot))
+                            .selected_text(spell_name(*spell))
+                            .show_ui(ui, |ui| {
+                                for (id, name) in SUMMONER_SPELLS {
+                                    ui.selectable_value(spell, id, name);
+                                }
+                            });
+                    }
+                    if ui.button("删除").clicked() {
+                        remove = Some(idx);
+                    }
+                    ui.end_row();
+                }
+            });
+        if let Some(idx) = remove {
+            loadout.spells.remove(idx);
+        }
+
+        ui.separator();
+        ui.horizontal(|ui| {
+            if ui.button("添加预设").clicked() {
+                loadout.spells.push(SpellPreset {
+                    spell1: 4,
+                    spell2: 14,
+                    ..Default::default()
+                });
+            }
+            ui.with_layout(Layout::right_to_left(Align::Center), |ui| {
+                if ui.button("关闭").clicked() {
+                    self.loadout_window = false;
+                }
+            });
+        });
+    }
+
+    /// 选择各功能的输出位置
+    fn output_settings_window(&mut self, ui: &mut egui::Ui) {
+        let mut outputs = CONTEXT.outputs.write().unwrap();
+        Grid::new("output_grid").striped(true).show(ui, |ui| {
+            ui.label("");
+            for sink in Sink::ALL {
+                ui.strong(sink.name());
+            }
+            ui.end_row();
+            for feature in Feature::ALL {
+                ui.label(feature.name());
+                let sinks = outputs.sinks_mut(feature);
+                for sink in Sink::ALL {
+                    ui.add_enabled(
+                        feature.supports(sink),
+                        Checkbox::without_text(sinks.get_mut(sink)),
+                    );
+                }
+                ui.end_row();
+            }
+        });
+        ui.separator();
+        ui.with_layout(Layout::right_to_left(Align::Center), |ui| {
+            if ui.button("关闭").clicked() {
+                self.output_window = false;
+            }
+        });
+    }
+
+    /// 显示输出到界面的队友分析、对局报告和赛后报告
+    fn analysis_report_window(&mut self, ui: &mut egui::Ui) {
+        ui.heading("队友分析");
+        let team_analysis = CONTEXT.team_analysis.read().unwrap();
+        if team_analysis.is_empty() {
+            ui.label("暂无队友分析，进入英雄选择后自动分析");
+        }
+        let templates = CONTEXT.analysis_templates.read().unwrap();
+        for score in team_analysis.iter() {
+            ui.label(templates.render(score));
+            ui.add_space(FRAME_MARGIN);
+        }
+        ui.separator();
+        ui.heading("对局报告");
+        match &*CONTEXT.lobby_report.read().unwrap() {
+            None => {
+                ui.label("暂无对局报告，游戏开始后自动分析");
+            }
+            Some(report) if report.allies.is_empty() && report.enemies.is_empty() => {
+                ui.label("正在分析对局中的玩家...");
+            }
+            Some(report) => {
+                for (team, players) in [("我方", &report.allies), ("敌方", &report.enemies)] {
+                    ui.heading(team);
+                    Grid::new(team).striped(true).show(ui, |ui| {
+                        for header in [
+                            "英雄",
+                            "玩家",
+                            "段位",
+                            "胜率",
+                            "KDA",
+                            "本英雄",
+                            "连胜/连败",
+                            "组队",
+                            "遇到过",
+                        ] {
+                            ui.strong(header);
+                        }
+                        ui.end_row();
+                        for player in players {
+                            ui.label(&player.champion_name);
+                            let Some(score) = &player.score else {
+                                ui.label("无战绩数据");
+                                ui.end_row();
+                                continue;
+                            };
+                            let (games, wins) = player.champion_record();
+                            ui.label(&score.name);
+                            ui.label(player.rank().unwrap_or_else(|| "未定级".to_string()));
+                            ui.label(format!("{}/{}", score.wins, score.total));
+                            ui.label(format!("{:.2}", score.kda));
+                            ui.label(format!("{games}场{wins}胜"));
+                            if score.repeats > 0 {
+                                ui.label(format!("{}连胜", score.repeats));
+                            } else {
+                                ui.label(format!("{}连败", -score.repeats));
+                            }
+                            ui.label(score.premade.map_or(String::new(), |id| format!("#{id}")));
+                            ui.label(
+                                score
+                                    .encounters
+                                    .map_or(String::new(), |encounters| encounters.to_string()),
+                            );
+                            ui.end_row();
+                        }
+                    });
+                    ui.add_space(FRAME_MARGIN);
+                }
+            }
+        }
+        ui.separator();
+        ui.heading("赛后报告");
+        match &*CONTEXT.post_game_report.read().unwrap() {
+            None => {
+                ui.label("暂无赛后报告，游戏结束后自动生成");
+            }
+            Some(report) => {
+                ui.label(report.to_string());
+                if ui.button("导出Markdown").clicked() {
+                    export_post_game_markdown(report);
+                }
+            }
+        }
+        ui.separator();
+        ui.with_layout(Layout::right_to_left(Align::Center), |ui| {
+            if ui.button("关闭").clicked() {
+                self.report_window = false;
+            }
+            if ui.button("历史赛后报告").clicked() {
+                self.post_game_history = Some(PostGameHistory::load());
+            }
+        });
+    }
+
+    /// 编辑玩家备注、标签和黑名单，可导入导出为 JSON 文件
+    fn note_editor_window(&mut self, ui: &mut egui::Ui) {
+        let Some(editor) = &mut self.note_editor else {
+            return;
+        };
+        let mut reload = false;
+        ScrollArea::vertical().max_height(200.0).show(ui, |ui| {
+            Grid::new("notes_grid").striped(true).show(ui, |ui| {
+                for header in ["玩家", "标签", "备注", "黑名单", ""] {
+                    ui.strong(header);
+                }
+                ui.end_row();
+                for note in &editor.notes {
+                    ui.label(&note.name);
+                    ui.label(note.tags.join("、"));
+                    ui.label(&note.note);
+                    ui.label(if note.blocked { "是" } else { "" });
+                    ui.horizontal(|ui| {
+                        if ui.button("编辑").clicked() {
+                            editor.tags = note.tags.join(",");
+                            editor.editing = note.clone();
+                        }
+                        if ui.button("删除").clicked() {
+                            if let Err(e) = NOTES.remove(&note.puuid) {
+                                error!("删除玩家标记失败: {e}");
+                            }
+                            reload = true;
+                        }
+                    });
+                    ui.end_row();
+                }
+            });
+        });
+        ui.separator();
+
+        let players = recent_players();
+        egui::ComboBox::from_id_salt("recent_players")
+            .selected_text("从最近分析的玩家中选择")
+            .show_ui(ui, |ui| {
+                for (puuid, name) in players {
+                    if ui.selectable_label(false, &name).clicked() {
+                        let note = NOTES.get(&puuid).unwrap_or(PlayerNote {
+                            puuid,
+                            name,
+                            ..Default::default()
+                        });
+                        editor.edit(note);
+                    }
+                }
+            });
+        Grid::new("note_form").num_columns(2).show(ui, |ui| {
+            ui.label("PUUID");
+            ui.text_edit_singleline(&mut editor.editing.puuid);
+            ui.end_row();
+            ui.label("名称");
+            ui.text_edit_singleline(&mut editor.editing.name);
+            ui.end_row();
+            ui.label("标签");
+            ui.text_edit_singleline(&mut editor.tags)
+                .on_hover_text("多个标签用逗号分隔");
+            ui.end_row();
+            ui.label("备注");
+            ui.text_edit_singleline(&mut editor.editing.note);
+            ui.end_row();
+            ui.label("黑名单");
+            ui.add(Checkbox::without_text(&mut editor.editing.blocked));
+            ui.end_row();
+        });
+        let can_save = !editor.editing.puuid.trim().is_empty();
+        if ui
+            .add_enabled(can_save, egui::Button::new("保存"))
+            .clicked()
+        {
+            let mut note = std::mem::take(&mut editor.editing);
+            note.puuid = note.puuid.trim().to_string();
+            note.tags = PlayerNote::parse_tags(&editor.tags);
+            editor.tags.clear();
+            if let Err(e) = NOTES.save(note) {
+                error!("保存玩家标记失败: {e}");
+            }
+            reload = true;
+        }
+
+        ui.separator();
+        ui.with_layout(Layout::right_to_left(Align::Center), |ui| {
+            if ui.button("关闭").clicked() {
+                self.note_editor = None;
+                return;
+            }
+            if ui.button("导出").clicked()
+                && let Some(path) = FileDialog::new()
+                    .add_filter("JSON", &["json"])
+                    .set_file_name("player_notes.json")
+                    .save_file()
+            {
+                match export_notes(&path) {
+                    Ok(count) => info!("已导出{count}条玩家标记到 {}", path.display()),
+                    Err(e) => error!("导出玩家标记失败: {e}"),
+                }
+            }
+            if ui.button("导入").clicked()
+                && let Some(path) = FileDialog::new().add_filter("JSON", &["json"]).pick_file()
+            {
+                match import_notes(&path) {
+                    Ok(count) => info!("已导入{count}条玩家标记"),
+                    Err(e) => error!("导入玩家标记失败: {e}"),
+                }
+                reload = true;
+            }
+        });
+        if reload && let Some(editor) = &mut self.note_editor {
+            editor.notes = NoteEditor::load().notes;
+        }
+    }
+
+    /// 标记过的玩家出现时的提醒，确认后清除
+    fn player_alerts_window(&mut self, ui: &mut egui::Ui) {
+        ui.heading("玩家提醒");
+        for alert in CONTEXT.player_alerts.read().unwrap().iter() {
+            if alert.contains("黑名单") {
+                ui.colored_label(Color32::RED, alert);
+            } else {
+                ui.label(alert);
+            }
+        }
+        ui.separator();
+        ui.with_layout(Layout::right_to_left(Align::Center), |ui| {
+            if ui.button("知道了").clicked() {
+                CONTEXT.player_alerts.write().unwrap().clear();
+            }
+        });
+    }
+
+    /// 查看本地保存的赛后报告
+    fn post_game_history_window(&mut self, ui: &mut egui::Ui) {
+        let Some(history) = &mut self.post_game_history else {
+            return;
+        };
+        if history.reports.is_empty() {
+            ui.label("暂无赛后报告");
+        } else {
+            history.selected = history.selected.min(history.reports.len() - 1);
+            egui::ComboBox::from_id_salt("post_game_report")
+                .selected_text(history.reports[history.selected].title())
+                .show_ui(ui, |ui| {
+                    for (index, report) in history.reports.iter().enumerate() {
+                        ui.selectable_value(&mut history.selected, index, report.title());
+                    }
+                });
+            let report = &history.reports[history.selected];
+            ui.label(report.to_string());
+            if ui.button("导出Markdown").clicked() {
+                export_post_game_markdown(report);
+            }
+        }
+        ui.separator();
+        ui.with_layout(Layout::right_to_left(Align::Center), |ui| {
+            if ui.button("关闭").clicked() {
+                self.post_game_history = None;
+            } else if ui.button("刷新").clicked() {
+                self.post_game_history = Some(PostGameHistory::load());
+            }
+        });
+    }
+
+    /// 编辑各语言的分析模板，使用示例数据实时预览
+    fn template_editor_window(&mut self, ui: &mut egui::Ui) {
+        let Some(editor) = &mut self.template_editor else {
+            return;
+        };
+        let languages = {
+            let templates = CONTEXT.analysis_templates.read().unwrap();
+            templates.templates.keys().cloned().collect::<Vec<_>>()
+        };
+        ui.horizontal(|ui| {
+            ui.label("语言");
+            let mut language = editor.language.clone();
+            egui::ComboBox::from_id_salt("template_language")
+                .selected_text(&language)
+                .show_ui(ui, |ui| {
+                    for option in &languages {
+                        ui.selectable_value(&mut language, option.clone(), option);
+                    }
+                });
+            if language != editor.language {
+                *editor = TemplateEditor::load(&language);
+            }
+            ui.add(egui::TextEdit::singleline(&mut editor.language).desired_width(60.0));
+        });
+        ui.add(
+            egui::TextEdit::multiline(&mut editor.source)
+                .desired_rows(4)
+                .desired_width(f32::INFINITY),
+        );
+        ui.collapsing("可用占位符", |ui| {
+            for (name, description) in PlayerScore::PLACEHOLDERS {
+                ui.label(format!("{{{name}}}  {description}"));
+            }
+            ui.label("{#name}...{/name} 数据非零时显示，{^name}...{/name} 数据为零时显示");
+        });
+
+        ui.separator();
+        ui.label("预览");
+        match Template::parse(&editor.source, PlayerScore::PLACEHOLDERS) {
+            Ok(template) => ui.label(template.render(&PlayerScore::sample())),
+            Err(e) => ui.colored_label(Color32::RED, e.to_string()),
+        };
+        if let Some(error) = &editor.error {
+            ui.colored_label(Color32::RED, error);
+        }
+
+        ui.separator();
+        let mut close = false;
+        ui.horizontal(|ui| {
+            let mut templates = CONTEXT.analysis_templates.write().unwrap();
+            if ui.button("保存并使用").clicked() {
+                let language = editor.language.trim().to_string();
+                match templates.set::<PlayerScore>(&language, &editor.source) {
+                    Ok(()) => {
+                        templates.language = language;
+                        editor.error = None;
+                    }
+                    Err(e) => editor.error = Some(format!("保存失败: {e}")),
+                }
+            }
+            if ui.button("恢复默认").clicked() {
+                *templates = AnalysisTemplates::default();
+                *editor = TemplateEditor {
+                    language: templates.language.clone(),
+                    source: templates.current().to_string(),
+                    error: None,
+                };
+            }
+            ui.with_layout(Layout::right_to_left(Align::Center), |ui| {
+                if ui.button("关闭").clicked() {
+                    close = true;
+                }
+            });
+        });
+        if close {
+            self.template_editor = None;
+        }
+    }
+
     fn render_modal(&self, ui: &mut egui::Ui, message: &str) -> bool {
         let modal = Modal::new(Id::new("message")).show(ui.ctx(), |ui| {
             ui.label(message);
@@ -520,28 +1658,33 @@ impl MyApp {
         }
     }
 
-    fn handle_champion_operations(&mut self, state: ChampionPickState) {
-        let mut auto_pick = CONTEXT.auto_pick.write().unwrap();
-        match (
-            state.select_index,
-            state.drag_from,
-            state.drag_to,
-            state.unselect_index,
-        ) {
-            // 从未选中列表选择英雄
-            (Some(from), None, None, None) => {
-                move_item_safe!(auto_pick.unselected, auto_pick.selected, from);
-            }
-            // 拖拽重排序
-            (None, Some(from), Some(to), None) if from != to => {
-                move_item_safe!(auto_pick.selected, auto_pick.selected, from, to);
-            }
-            // 取消选择英雄
-            (None, None, None, Some(from)) => {
-                move_item_safe!(auto_pick.selected, auto_pick.unselected, from);
+    fn handle_champion_operations(list: ChampionList, state: ChampionPickState) {
+        list.with_selected(|selected| {
+            match (
+                state.select_id,
+                state.drag_from,
+                state.drag_to,
+                state.unselect_index,
+            ) {
+                // 从可用英雄中选择，放到列表开头
+                (Some(id), None, None, None) => {
+                    if let Some(champion) =
+                        CONTEXT.champions.read().unwrap().iter().find(|c| c.0 == id)
+                    {
+                        selected.insert(0, champion.clone());
+                    }
+                }
+                // 拖拽重排序
+                (None, Some(from), Some(to), None) if from != to => {
+                    move_item_safe!(selected, selected, from, to);
+                }
+                // 取消选择英雄
+                (None, None, None, Some(from)) if from < selected.len() => {
+                    selected.remove(from);
+                }
+                // 其他情况不处理（避免冲突）
+                _ => {}
             }
-            // 其他情况不处理（避免冲突）
-            _ => {}
-        }
+        });
     }
 }